@@ -0,0 +1,251 @@
+//! Tron account addresses.
+//!
+//! A Tron address is 21 bytes: the `0x41` network prefix followed by the
+//! 20-byte account id shared with the EVM. It is usually displayed either as
+//! Base58Check (`T...`) or as 42 hex characters (`41...`).
+
+use crate::error::{Error, Result};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix byte of every Tron mainnet/testnet address.
+pub const ADDRESS_PREFIX: u8 = 0x41;
+
+/// Length of an address in bytes, including the prefix.
+pub const ADDRESS_LEN: usize = 21;
+
+/// An address as given by the caller, in either of its text forms. The
+/// string is checked when the address is converted, see
+/// [`Address::to_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// Base58Check, e.g. `TRLpnm6Uz9s2Fcy3Q235k3SiAEBXGJCNq2`.
+    Base58(String),
+    /// Hex with the `41` prefix, e.g. `41a8a07f09def5e6a4462df90068c11abf6224e865`.
+    Hex(String),
+}
+
+fn invalid<S: Into<String>>(msg: S) -> Error {
+    Error::InvalidAddress(msg.into())
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(Sha256::digest(payload));
+    let mut out = [0u8; 4];
+    out.copy_from_slice(&hash[..4]);
+    out
+}
+
+fn strip_0x(s: &str) -> &str {
+    s.strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s)
+}
+
+impl Address {
+    /// Builds an address from its 21 raw bytes (prefix included).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Address> {
+        if bytes.len() != ADDRESS_LEN {
+            return Err(invalid(format!(
+                "expected {} bytes, got {}",
+                ADDRESS_LEN,
+                bytes.len()
+            )));
+        }
+        if bytes[0] != ADDRESS_PREFIX {
+            return Err(invalid(format!("unexpected prefix 0x{:02x}", bytes[0])));
+        }
+        Ok(Address::Hex(hex::encode(bytes)))
+    }
+
+    /// Builds an address from a 20-byte EVM address, adding the `0x41` prefix.
+    pub fn from_evm_bytes(bytes: &[u8]) -> Result<Address> {
+        if bytes.len() != ADDRESS_LEN - 1 {
+            return Err(invalid(format!(
+                "expected {} bytes, got {}",
+                ADDRESS_LEN - 1,
+                bytes.len()
+            )));
+        }
+        let mut raw = Vec::with_capacity(ADDRESS_LEN);
+        raw.push(ADDRESS_PREFIX);
+        raw.extend_from_slice(bytes);
+        Address::from_bytes(&raw)
+    }
+
+    /// Parses a 20-byte EVM hex address (with or without `0x`).
+    pub fn from_evm_hex(s: &str) -> Result<Address> {
+        let bytes = hex::decode(strip_0x(s)).map_err(|e| invalid(e.to_string()))?;
+        Address::from_evm_bytes(&bytes)
+    }
+
+    /// Decodes and validates the address, returning its 21 raw bytes.
+    ///
+    /// Base58 addresses have their checksum verified, and both forms must
+    /// carry the `0x41` prefix.
+    pub fn to_bytes(&self) -> Result<[u8; ADDRESS_LEN]> {
+        let bytes = match self {
+            Address::Base58(s) => {
+                let decoded = bs58::decode(s)
+                    .into_vec()
+                    .map_err(|e| invalid(e.to_string()))?;
+                if decoded.len() != ADDRESS_LEN + 4 {
+                    return Err(invalid(format!(
+                        "expected {} bytes, got {}",
+                        ADDRESS_LEN + 4,
+                        decoded.len()
+                    )));
+                }
+                let (payload, check) = decoded.split_at(ADDRESS_LEN);
+                if checksum(payload) != check {
+                    return Err(invalid("bad base58 checksum"));
+                }
+                payload.to_vec()
+            }
+            Address::Hex(s) => hex::decode(strip_0x(s)).map_err(|e| invalid(e.to_string()))?,
+        };
+
+        if bytes.len() != ADDRESS_LEN {
+            return Err(invalid(format!(
+                "expected {} bytes, got {}",
+                ADDRESS_LEN,
+                bytes.len()
+            )));
+        }
+        if bytes[0] != ADDRESS_PREFIX {
+            return Err(invalid(format!("unexpected prefix 0x{:02x}", bytes[0])));
+        }
+
+        let mut out = [0u8; ADDRESS_LEN];
+        out.copy_from_slice(&bytes);
+        Ok(out)
+    }
+
+    /// Returns an error if the address is malformed.
+    pub fn validate(&self) -> Result<()> {
+        self.to_bytes().map(|_| ())
+    }
+
+    /// Base58Check representation, e.g. `TRLpnm6Uz9s2Fcy3Q235k3SiAEBXGJCNq2`.
+    pub fn to_base58(&self) -> Result<String> {
+        let bytes = self.to_bytes()?;
+        let mut data = bytes.to_vec();
+        data.extend_from_slice(&checksum(&bytes));
+        Ok(bs58::encode(data).into_string())
+    }
+
+    /// Lowercase 42 character hex representation, e.g. `41a8a0...e865`.
+    pub fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(self.to_bytes()?))
+    }
+
+    /// 20-byte EVM representation, e.g. `0xa8a0...e865`.
+    pub fn to_evm_hex(&self) -> Result<String> {
+        Ok(format!("0x{}", hex::encode(&self.to_bytes()?[1..])))
+    }
+
+    /// Converts to the `Base58` variant.
+    pub fn into_base58(self) -> Result<Address> {
+        Ok(Address::Base58(self.to_base58()?))
+    }
+
+    /// Converts to the `Hex` variant.
+    pub fn into_hex(self) -> Result<Address> {
+        Ok(Address::Hex(self.to_hex()?))
+    }
+
+    /// Whether the node should be told the address is in base58
+    /// (the `visible` flag of most `/wallet` endpoints).
+    pub fn is_visible(&self) -> bool {
+        matches!(self, Address::Base58(_))
+    }
+
+    /// The address string as it was given, without any conversion.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Address::Base58(s) | Address::Hex(s) => s,
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    /// Accepts Base58Check, 41-prefixed hex and 20-byte EVM hex, with or
+    /// without `0x`. The result is validated.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let unprefixed = strip_0x(s);
+        let address = if s.starts_with('T') && s.len() == 34 {
+            Address::Base58(s.to_string())
+        } else if unprefixed.len() == (ADDRESS_LEN - 1) * 2 {
+            return Address::from_evm_hex(unprefixed);
+        } else {
+            Address::Hex(unprefixed.to_lowercase())
+        };
+        address.validate()?;
+        Ok(address)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE58: &str = "TRLpnm6Uz9s2Fcy3Q235k3SiAEBXGJCNq2";
+    const HEX: &str = "41a8a07f09def5e6a4462df90068c11abf6224e865";
+
+    #[test]
+    fn base58_to_hex() {
+        let addr = Address::Base58(BASE58.into());
+        assert_eq!(addr.to_hex().unwrap(), HEX);
+        assert_eq!(
+            addr.to_evm_hex().unwrap(),
+            "0xa8a07f09def5e6a4462df90068c11abf6224e865"
+        );
+    }
+
+    #[test]
+    fn hex_to_base58() {
+        let addr = Address::Hex(HEX.to_uppercase());
+        assert_eq!(addr.to_base58().unwrap(), BASE58);
+        let addr = Address::from_evm_hex("0xa8a07f09def5e6a4462df90068c11abf6224e865").unwrap();
+        assert_eq!(addr.to_base58().unwrap(), BASE58);
+    }
+
+    #[test]
+    fn parse_any_form() {
+        let expected = Address::Base58(BASE58.into()).to_bytes().unwrap();
+        for s in &[
+            BASE58,
+            HEX,
+            "0x41a8a07f09def5e6a4462df90068c11abf6224e865",
+            "a8a07f09def5e6a4462df90068c11abf6224e865",
+        ] {
+            let addr: Address = s.parse().unwrap();
+            assert_eq!(addr.to_bytes().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        let addr = Address::Base58("TRLpnm6Uz9s2Fcy3Q235k3SiAEBXGJCNq3".into());
+        assert!(addr.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_bad_prefix() {
+        let addr = Address::Hex("42a8a07f09def5e6a4462df90068c11abf6224e865".into());
+        assert!(addr.validate().is_err());
+        assert!("0x42a8a07f09def5e6a4462df90068c11abf6224e865"
+            .parse::<Address>()
+            .is_err());
+    }
+}
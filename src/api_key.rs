@@ -0,0 +1,205 @@
+//! Rotation over several TronGrid API keys.
+//!
+//! An [`ApiKeyPool`] hands out its keys round-robin. A key TronGrid
+//! refuses, as unknown or over its rate limit, is marked exhausted and
+//! skipped until its cooldown ends, and the client sends the request again
+//! right away with the next available key. Other 401 and 403 responses,
+//! e.g. from a gateway checking [`Auth`](../auth/enum.Auth.html)
+//! credentials, leave the key alone. If every key is exhausted, the one that recovers first is used
+//! anyway.
+//! Request counts per key are kept to see which key is burning quota.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default time an exhausted key is skipped.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct KeyState {
+    key: String,
+    requests: AtomicU64,
+    rejections: AtomicU64,
+    exhausted_until: Mutex<Option<Instant>>,
+}
+
+/// Usage of one key of an [`ApiKeyPool`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyStats {
+    /// Position of the key in the pool.
+    pub index: usize,
+    /// First and last characters of the key.
+    pub key_hint: String,
+    /// Requests sent with the key.
+    pub requests: u64,
+    /// Responses refusing the key, as unknown or over its rate limit.
+    pub rejections: u64,
+    /// Whether the key is currently cooling down.
+    pub exhausted: bool,
+}
+
+/// A set of API keys used in turn. Shared between clones of a
+/// [`Client`](../client/struct.Client.html).
+#[derive(Debug)]
+pub struct ApiKeyPool {
+    keys: Vec<KeyState>,
+    next: AtomicUsize,
+    cooldown: Duration,
+}
+
+fn key_hint(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
+impl ApiKeyPool {
+    pub fn new<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        ApiKeyPool {
+            keys: keys
+                .into_iter()
+                .map(|key| KeyState {
+                    key: key.into(),
+                    requests: AtomicU64::new(0),
+                    rejections: AtomicU64::new(0),
+                    exhausted_until: Mutex::new(None),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+            cooldown: DEFAULT_COOLDOWN,
+        }
+    }
+
+    /// How long an exhausted key is skipped.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Picks the next available key and counts a request for it. Returns
+    /// its index, for [`mark_exhausted`](#method.mark_exhausted), and the
+    /// key.
+    pub fn next_key(&self) -> Option<(usize, &str)> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let now = Instant::now();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut soonest: Option<(usize, Instant)> = None;
+
+        let mut chosen = None;
+        for offset in 0..self.keys.len() {
+            let index = (start + offset) % self.keys.len();
+            let mut until = self.keys[index].exhausted_until.lock().unwrap();
+            match *until {
+                Some(t) if t > now => {
+                    if soonest.map(|(_, s)| t < s).unwrap_or(true) {
+                        soonest = Some((index, t));
+                    }
+                }
+                _ => {
+                    *until = None;
+                    chosen = Some(index);
+                    break;
+                }
+            }
+        }
+
+        let index = chosen.or_else(|| soonest.map(|(index, _)| index))?;
+        let key = &self.keys[index];
+        key.requests.fetch_add(1, Ordering::Relaxed);
+        Some((index, &key.key))
+    }
+
+    /// Whether some key is not cooling down.
+    pub fn has_available_key(&self) -> bool {
+        let now = Instant::now();
+        self.keys
+            .iter()
+            .any(|key| key.exhausted_until.lock().unwrap().is_none_or(|t| t <= now))
+    }
+
+    /// Skips the key at `index` for the cooldown period.
+    pub fn mark_exhausted(&self, index: usize) {
+        if let Some(key) = self.keys.get(index) {
+            key.rejections.fetch_add(1, Ordering::Relaxed);
+            *key.exhausted_until.lock().unwrap() = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    /// Usage of every key, in pool order.
+    pub fn stats(&self) -> Vec<ApiKeyStats> {
+        let now = Instant::now();
+        self.keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| ApiKeyStats {
+                index,
+                key_hint: key_hint(&key.key),
+                requests: key.requests.load(Ordering::Relaxed),
+                rejections: key.rejections.load(Ordering::Relaxed),
+                exhausted: key
+                    .exhausted_until
+                    .lock()
+                    .unwrap()
+                    .map(|t| t > now)
+                    .unwrap_or(false),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_and_skips_exhausted_keys() {
+        let pool = ApiKeyPool::new(vec!["key-a", "key-b", "key-c"]);
+        let keys: Vec<_> = (0..3).map(|_| pool.next_key().unwrap().1).collect();
+        assert_eq!(keys, vec!["key-a", "key-b", "key-c"]);
+
+        pool.mark_exhausted(1);
+        let keys: Vec<_> = (0..4).map(|_| pool.next_key().unwrap().1).collect();
+        assert_eq!(keys, vec!["key-a", "key-c", "key-c", "key-a"]);
+
+        let stats = pool.stats();
+        assert_eq!(stats[0].requests, 3);
+        assert_eq!(stats[1].requests, 1);
+        assert_eq!(stats[1].rejections, 1);
+        assert!(stats[1].exhausted);
+        assert_eq!(stats[2].key_hint, "*****");
+    }
+
+    #[test]
+    fn uses_soonest_key_when_all_are_exhausted() {
+        let pool = ApiKeyPool::new(vec!["0123456789", "abcdefghij"]);
+        pool.mark_exhausted(1);
+        assert!(pool.has_available_key());
+        std::thread::sleep(Duration::from_millis(5));
+        pool.mark_exhausted(0);
+        assert!(!pool.has_available_key());
+        assert_eq!(pool.next_key().unwrap().0, 1);
+        assert_eq!(pool.next_key().unwrap().0, 1);
+        assert_eq!(pool.stats()[0].key_hint, "0123...6789");
+
+        assert!(ApiKeyPool::new(Vec::<String>::new()).next_key().is_none());
+    }
+}
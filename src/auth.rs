@@ -0,0 +1,196 @@
+//! Authentication towards self-hosted nodes.
+//!
+//! TronGrid only needs an API key, but nodes behind a gateway may require
+//! HTTP credentials, set with [`ClientBuilder::auth`], or a client
+//! certificate, set with [`ClientBuilder::tls`]. Both apply to every
+//! request of the client, whatever the path (`/wallet`, `/walletsolidity`,
+//! `/walletpbft`, `/v1`) or the node it goes to.
+//!
+//! ```no_run
+//! # use tron_api_client::auth::{Auth, TlsConfig};
+//! # use tron_api_client::client::Client;
+//! let tls = TlsConfig::new()
+//!     .identity_pem_files("client.crt", "client.key")?
+//!     .root_certificate_file("ca.crt")?;
+//! let client = Client::builder()
+//!     .base_url("https://tron.internal")
+//!     .auth(Auth::bearer("secret-token"))
+//!     .tls(tls)
+//!     .build()?;
+//! # Ok::<(), tron_api_client::error::Error>(())
+//! ```
+//!
+//! [`ClientBuilder::auth`]: ../client/struct.ClientBuilder.html#method.auth
+//! [`ClientBuilder::tls`]: ../client/struct.ClientBuilder.html#method.tls
+
+use crate::error::{Error, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Certificate, ClientBuilder as HttpClientBuilder, Identity};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Credentials sent with every request.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// HTTP basic authentication.
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    /// `Authorization: Bearer <token>`.
+    Bearer(String),
+    /// Any other header, e.g. `X-Api-Token`.
+    Header { name: String, value: String },
+}
+
+impl Auth {
+    pub fn basic<U: Into<String>>(username: U, password: Option<String>) -> Self {
+        Auth::Basic {
+            username: username.into(),
+            password,
+        }
+    }
+
+    pub fn bearer<T: Into<String>>(token: T) -> Self {
+        Auth::Bearer(token.into())
+    }
+
+    pub fn header<N: Into<String>, V: Into<String>>(name: N, value: V) -> Self {
+        Auth::Header {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    /// The header carrying the credentials, marked sensitive so it is not
+    /// logged.
+    pub(crate) fn to_header(&self) -> Result<(HeaderName, HeaderValue)> {
+        let (name, value) = match self {
+            Auth::Basic { username, password } => {
+                let credentials = format!("{}:{}", username, password.as_deref().unwrap_or(""));
+                (
+                    AUTHORIZATION,
+                    format!("Basic {}", STANDARD.encode(credentials)),
+                )
+            }
+            Auth::Bearer(token) => (AUTHORIZATION, format!("Bearer {}", token)),
+            Auth::Header { name, value } => {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| Error::InvalidConfig(format!("auth header {}: {}", name, e)))?;
+                (name, value.clone())
+            }
+        };
+        let mut value = HeaderValue::from_str(&value)
+            .map_err(|e| Error::InvalidConfig(format!("auth header value: {}", e)))?;
+        value.set_sensitive(true);
+        Ok((name, value))
+    }
+}
+
+/// Keeps credentials out of logs.
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            Auth::Bearer(_) => f.write_str("Bearer(..)"),
+            Auth::Header { name, .. } => f
+                .debug_struct("Header")
+                .field("name", name)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Client certificate and trusted roots for mutual TLS.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    identity: Option<Identity>,
+    root_certificates: Vec<Certificate>,
+}
+
+fn tls_error(e: reqwest::Error) -> Error {
+    Error::InvalidConfig(format!("tls: {}", e))
+}
+
+impl TlsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Presents the PEM certificate chain `cert` with its PKCS #8 PEM `key`.
+    pub fn identity_pem(mut self, cert: &[u8], key: &[u8]) -> Result<Self> {
+        self.identity = Some(Identity::from_pkcs8_pem(cert, key).map_err(tls_error)?);
+        Ok(self)
+    }
+
+    /// Like [`TlsConfig::identity_pem`], reading both from files.
+    pub fn identity_pem_files<P: AsRef<Path>, K: AsRef<Path>>(
+        self,
+        cert: P,
+        key: K,
+    ) -> Result<Self> {
+        self.identity_pem(&fs::read(cert)?, &fs::read(key)?)
+    }
+
+    /// Presents the identity in a DER-encoded PKCS #12 archive.
+    pub fn identity_pkcs12(mut self, der: &[u8], password: &str) -> Result<Self> {
+        self.identity = Some(Identity::from_pkcs12_der(der, password).map_err(tls_error)?);
+        Ok(self)
+    }
+
+    /// Trusts the PEM certificate `pem` as root, besides the system roots.
+    pub fn root_certificate_pem(mut self, pem: &[u8]) -> Result<Self> {
+        self.root_certificates
+            .push(Certificate::from_pem(pem).map_err(tls_error)?);
+        Ok(self)
+    }
+
+    /// Like [`TlsConfig::root_certificate_pem`], reading it from a file.
+    pub fn root_certificate_file<P: AsRef<Path>>(self, path: P) -> Result<Self> {
+        self.root_certificate_pem(&fs::read(path)?)
+    }
+
+    pub(crate) fn apply(self, mut builder: HttpClientBuilder) -> HttpClientBuilder {
+        if let Some(identity) = self.identity {
+            builder = builder.identity(identity);
+        }
+        for certificate in self.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_headers() {
+        let (name, value) = Auth::basic("tron", Some("secret".into()))
+            .to_header()
+            .unwrap();
+        assert_eq!(name, AUTHORIZATION);
+        assert_eq!(value, "Basic dHJvbjpzZWNyZXQ=");
+        assert!(value.is_sensitive());
+
+        let (_, value) = Auth::bearer("token").to_header().unwrap();
+        assert_eq!(value, "Bearer token");
+
+        let (name, value) = Auth::header("X-Api-Token", "abc").to_header().unwrap();
+        assert_eq!(
+            (name.as_str(), value.to_str().unwrap()),
+            ("x-api-token", "abc")
+        );
+        assert!(Auth::header("bad header", "abc").to_header().is_err());
+
+        assert!(!format!("{:?}", Auth::bearer("token")).contains("token"));
+        assert!(TlsConfig::new().root_certificate_pem(b"not a pem").is_err());
+    }
+}
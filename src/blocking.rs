@@ -0,0 +1,428 @@
+//! A synchronous client, enabled with the `blocking` feature.
+//!
+//! [`Client`] mirrors the async [`crate::Client`] method for method and
+//! takes the same params and returns the same response types and errors.
+//! Each call blocks on the async method in a runtime owned by the client,
+//! with one worker thread so background tasks such as health checks keep
+//! running between calls. Like `reqwest::blocking`, it must not be used
+//! from within an async runtime.
+//!
+//! ```no_run
+//! # use tron_api_client::blocking::Client;
+//! # use tron_api_client::client::Network;
+//! let client = Client::for_network(Network::Shasta, None);
+//! let block = client.get_now_block().unwrap();
+//! println!("{}", block.block_header.raw_data.number);
+//! ```
+
+use crate::api_key::ApiKeyStats;
+use crate::cache::CacheStats;
+use crate::client::{self, ClientBuilder, Network};
+use crate::confirmation::Confirmation;
+use crate::error::{Error, Result};
+use crate::failover::EndpointHealth;
+use crate::response::{
+    Account, AccountNet, AccountResource, AssetIssueList, Block, BlockList, BroadcastHexResponse,
+    BroadcastTransactionResponse, ChainParameters, ConstantContractResponse, Contract,
+    EstimateEnergyResponse, NodeInfo, NodeList, Transaction, TransactionInfo, TransferEvent,
+    WitnessList,
+};
+use crate::{Address, TxId};
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+
+/// Synchronous counterpart of [`crate::Client`]. Clones share the runtime,
+/// the connection pool and the rate limiter.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: client::Client,
+    runtime: Arc<Runtime>,
+}
+
+impl ClientBuilder {
+    /// Creates a [`blocking::Client`](struct.Client.html).
+    pub fn build_blocking(self) -> Result<Client> {
+        Client::from_async(self.build()?)
+    }
+}
+
+impl Client {
+    /// Wraps an async client, starting a runtime for it.
+    pub fn from_async(inner: client::Client) -> Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("tron-api-client-blocking")
+            .enable_all()
+            .build()
+            .map_err(Error::IO)?;
+        Ok(Client {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Panics if `base_url` cannot be parsed or the runtime cannot start.
+    pub fn new(base_url: String, api_key: Option<String>) -> Self {
+        Self::try_new(base_url, api_key).expect("could not create client")
+    }
+
+    pub fn try_new(base_url: String, api_key: Option<String>) -> Result<Self> {
+        Self::from_async(client::Client::try_new(base_url, api_key)?)
+    }
+
+    /// Configure with [`ClientBuilder::build_blocking`].
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    pub fn for_network(network: Network, api_key: Option<String>) -> Self {
+        Self::from_async(client::Client::for_network(network, api_key))
+            .expect("could not create client")
+    }
+
+    pub fn for_shasta(api_key: Option<String>) -> Self {
+        Self::for_network(Network::Shasta, api_key)
+    }
+
+    pub fn for_main(api_key: Option<String>) -> Self {
+        Self::for_network(Network::Main, api_key)
+    }
+
+    /// The async client behind this one.
+    pub fn as_async(&self) -> &client::Client {
+        &self.inner
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    pub fn api_key_stats(&self) -> Vec<ApiKeyStats> {
+        self.inner.api_key_stats()
+    }
+
+    pub fn with_confirmation(&self, confirmation: Confirmation) -> Client {
+        Client {
+            inner: self.inner.with_confirmation(confirmation),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.inner.cache_stats()
+    }
+
+    pub fn verify_chain(&self) -> Result<()> {
+        self.block_on(self.inner.verify_chain())
+    }
+
+    pub fn check_health(&self) -> Vec<EndpointHealth> {
+        self.block_on(self.inner.check_health())
+    }
+
+    /// Runs health checks on the client's runtime until the returned task
+    /// is aborted.
+    pub fn spawn_health_checks(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let _guard = self.runtime.enter();
+        self.inner.spawn_health_checks(interval)
+    }
+
+    pub fn endpoint_health(&self) -> Vec<EndpointHealth> {
+        self.inner.endpoint_health()
+    }
+
+    pub fn post<T, U>(&self, path: &str, param: U) -> Result<T>
+    where
+        T: DeserializeOwned,
+        U: Serialize,
+    {
+        self.block_on(self.inner.post(path, param))
+    }
+
+    pub fn get<T>(&self, path: &str) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.block_on(self.inner.get(path))
+    }
+
+    pub fn get_node_info(&self) -> Result<NodeInfo> {
+        self.block_on(self.inner.get_node_info())
+    }
+
+    pub fn list_nodes(&self) -> Result<NodeList> {
+        self.block_on(self.inner.list_nodes())
+    }
+
+    pub fn list_witnesses(&self) -> Result<WitnessList> {
+        self.block_on(self.inner.list_witnesses())
+    }
+
+    pub fn get_chain_parameters(&self) -> Result<ChainParameters> {
+        self.block_on(self.inner.get_chain_parameters())
+    }
+
+    pub fn get_block_by_num(&self, num: u64) -> Result<Block> {
+        self.block_on(self.inner.get_block_by_num(num))
+    }
+
+    /// Blocks are returned in input order; there is no stream variant.
+    pub fn get_blocks_by_num<I>(&self, nums: I, concurrency: usize) -> Vec<Result<Block>>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        self.block_on(self.inner.get_blocks_by_num(nums, concurrency))
+    }
+
+    pub fn get_block_by_id(&self, id: &str) -> Result<Block> {
+        self.block_on(self.inner.get_block_by_id(id))
+    }
+
+    pub fn get_now_block(&self) -> Result<Block> {
+        self.block_on(self.inner.get_now_block())
+    }
+
+    pub fn get_now_block_solidity(&self) -> Result<Block> {
+        self.block_on(self.inner.get_now_block_solidity())
+    }
+
+    pub fn get_block_by_latest_num(&self, num: u64) -> Result<BlockList> {
+        self.block_on(self.inner.get_block_by_latest_num(num))
+    }
+
+    pub fn get_block_by_limit_next(&self, start_num: u64, end_num: u64) -> Result<BlockList> {
+        self.block_on(self.inner.get_block_by_limit_next(start_num, end_num))
+    }
+
+    pub fn get_account(&self, address: Address) -> Result<Account> {
+        self.block_on(self.inner.get_account(address))
+    }
+
+    pub fn get_account_net(&self, address: Address) -> Result<AccountNet> {
+        self.block_on(self.inner.get_account_net(address))
+    }
+
+    pub fn get_transaction_by_id(&self, tx_id: TxId) -> Result<Transaction> {
+        self.block_on(self.inner.get_transaction_by_id(tx_id))
+    }
+
+    pub fn get_transaction_info_by_id(&self, tx_id: TxId) -> Result<TransactionInfo> {
+        self.block_on(self.inner.get_transaction_info_by_id(tx_id))
+    }
+
+    /// Results are returned in input order; there is no stream variant.
+    pub fn get_transaction_infos_by_id<I>(
+        &self,
+        tx_ids: I,
+        concurrency: usize,
+    ) -> Vec<Result<TransactionInfo>>
+    where
+        I: IntoIterator<Item = TxId>,
+    {
+        self.block_on(self.inner.get_transaction_infos_by_id(tx_ids, concurrency))
+    }
+
+    pub fn get_contract(&self, address: Address) -> Result<Contract> {
+        self.block_on(self.inner.get_contract(address))
+    }
+
+    pub fn get_asset_issue_list(&self) -> Result<AssetIssueList> {
+        self.block_on(self.inner.get_asset_issue_list())
+    }
+
+    pub fn get_transaction_info_by_block_num(
+        &self,
+        block_number: u64,
+    ) -> Result<Vec<TransactionInfo>> {
+        self.block_on(self.inner.get_transaction_info_by_block_num(block_number))
+    }
+
+    pub fn get_contract_events<T: DeserializeOwned>(
+        &self,
+        contract_address: &str,
+        event_name: &str,
+        block_number: Option<u64>,
+        min_block_timestamp: Option<u64>,
+        max_block_timestamp: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<T> {
+        self.block_on(self.inner.get_contract_events(
+            contract_address,
+            event_name,
+            block_number,
+            min_block_timestamp,
+            max_block_timestamp,
+            limit,
+        ))
+    }
+
+    pub fn get_contract_transfer_events(
+        &self,
+        contract_address: &str,
+        block_number: Option<u64>,
+        min_block_timestamp: Option<u64>,
+        max_block_timestamp: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<Vec<TransferEvent>> {
+        self.block_on(self.inner.get_contract_transfer_events(
+            contract_address,
+            block_number,
+            min_block_timestamp,
+            max_block_timestamp,
+            limit,
+        ))
+    }
+
+    pub fn trigger_constant_contract(
+        &self,
+        owner_address: &str,
+        contract_address: &str,
+        function_selector: &str,
+        parameter: &str,
+    ) -> Result<ConstantContractResponse> {
+        self.block_on(self.inner.trigger_constant_contract(
+            owner_address,
+            contract_address,
+            function_selector,
+            parameter,
+        ))
+    }
+
+    pub fn estimate_energy(
+        &self,
+        owner_address: &str,
+        contract_address: &str,
+        function_selector: &str,
+        parameter: &str,
+    ) -> Result<EstimateEnergyResponse> {
+        self.block_on(self.inner.estimate_energy(
+            owner_address,
+            contract_address,
+            function_selector,
+            parameter,
+        ))
+    }
+
+    pub fn get_account_resource(&self, address: &str) -> Result<AccountResource> {
+        self.block_on(self.inner.get_account_resource(address))
+    }
+
+    pub fn create_transaction(
+        &self,
+        owner_address: &str,
+        to_address: &str,
+        amount: i64,
+    ) -> Result<Transaction> {
+        self.block_on(
+            self.inner
+                .create_transaction(owner_address, to_address, amount),
+        )
+    }
+
+    pub fn broadcast_hex(&self, transaction: String) -> Result<BroadcastHexResponse> {
+        self.block_on(self.inner.broadcast_hex(transaction))
+    }
+
+    pub fn broadcast_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<BroadcastTransactionResponse> {
+        self.block_on(self.inner.broadcast_transaction(transaction))
+    }
+
+    pub fn freeze_balance_v2(
+        &self,
+        owner_address: &str,
+        resource: &str,
+        frozen_balance: i64,
+    ) -> Result<Transaction> {
+        self.block_on(
+            self.inner
+                .freeze_balance_v2(owner_address, resource, frozen_balance),
+        )
+    }
+
+    pub fn unfreeze_balance_v2(
+        &self,
+        owner_address: &str,
+        resource: &str,
+        unfreeze_balance: i64,
+    ) -> Result<Transaction> {
+        self.block_on(
+            self.inner
+                .unfreeze_balance_v2(owner_address, resource, unfreeze_balance),
+        )
+    }
+
+    pub fn delegate_resource(
+        &self,
+        owner_address: &str,
+        receiver_address: &str,
+        resource: &str,
+        balance: i64,
+        lock: bool,
+        lock_period: i64,
+    ) -> Result<Transaction> {
+        self.block_on(self.inner.delegate_resource(
+            owner_address,
+            receiver_address,
+            resource,
+            balance,
+            lock,
+            lock_period,
+        ))
+    }
+
+    pub fn undelegate_resource(
+        &self,
+        owner_address: &str,
+        receiver_address: &str,
+        resource: &str,
+        balance: i64,
+    ) -> Result<Transaction> {
+        self.block_on(self.inner.undelegate_resource(
+            owner_address,
+            receiver_address,
+            resource,
+            balance,
+        ))
+    }
+
+    pub fn activate_account(
+        &self,
+        owner_address: &str,
+        account_address: &str,
+    ) -> Result<Transaction> {
+        self.block_on(self.inner.activate_account(owner_address, account_address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+
+    #[test]
+    fn blocks_on_async_client() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond(
+            "POST",
+            "/wallet/getnowblock",
+            200,
+            include_str!("response/block.json"),
+        );
+        let client = ClientBuilder::new()
+            .base_url("http://node.test")
+            .transport(mock.clone())
+            .build_blocking()
+            .unwrap();
+
+        let block = client.get_now_block().unwrap();
+        assert_eq!(block.block_header.raw_data.number, 3_412_121);
+        assert!(client.clone().get_node_info().is_err());
+        assert_eq!(mock.requests().len(), 2);
+    }
+}
@@ -0,0 +1,251 @@
+//! Caching of immutable chain data.
+//!
+//! Blocks at or below the solidified head and the `TransactionInfo` of
+//! transactions in such blocks never change. A [`ResponseCache`] set on a
+//! [`Client`](../client/struct.Client.html) keeps them, keyed by endpoint
+//! and normalized params, either in memory with least-recently-used
+//! eviction or as files in a directory.
+//!
+//! Nothing else is cached: a response is only stored once the client has
+//! seen a solidified block at least as high as the block it belongs to, and
+//! only as served by `/walletsolidity`, so a block from a fork that was
+//! read from `/wallet` is never kept.
+//! The solidified head is refreshed from `/walletsolidity/getnowblock`
+//! when a newer block is looked up, at most once per block interval.
+
+use crate::cassette::normalize_body;
+use crate::error::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a known solidified head is trusted before a newer block
+/// triggers a refresh; one block is produced every 3 seconds.
+const SOLID_HEAD_TTL: Duration = Duration::from_secs(3);
+
+/// When a response may be cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Finality {
+    /// Immutable once the block with this number is solidified.
+    AtBlock(i64),
+}
+
+/// Hits and misses of a [`ResponseCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Responses stored so far.
+    pub stores: u64,
+    /// Entries currently held in memory; 0 for disk caches.
+    pub entries: usize,
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<String, (String, u64)>,
+    /// Last use of every key, oldest first.
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Lru {
+    fn get(&mut self, key: &str) -> Option<String> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (value, used) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        self.order.insert(tick, key.to_string());
+        *used = tick;
+        Some(value.clone())
+    }
+
+    fn put(&mut self, key: &str, value: String, capacity: usize) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(key.to_string(), (value, self.tick)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, key.to_string());
+        while self.entries.len() > capacity {
+            let oldest = match self.order.keys().next() {
+                Some(tick) => *tick,
+                None => break,
+            };
+            if let Some(key) = self.order.remove(&oldest) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Store {
+    Memory { capacity: usize, lru: Mutex<Lru> },
+    Disk { dir: PathBuf },
+}
+
+/// Cache of immutable responses, shared between clones of a client.
+#[derive(Debug)]
+pub struct ResponseCache {
+    store: Store,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stores: AtomicU64,
+    solid_head: AtomicI64,
+    solid_head_checked: Mutex<Option<Instant>>,
+}
+
+impl ResponseCache {
+    fn with_store(store: Store) -> Self {
+        ResponseCache {
+            store,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+            solid_head: AtomicI64::new(-1),
+            solid_head_checked: Mutex::new(None),
+        }
+    }
+
+    /// Keeps up to `capacity` responses in memory, evicting the least
+    /// recently used.
+    pub fn memory(capacity: usize) -> Self {
+        Self::with_store(Store::Memory {
+            capacity: capacity.max(1),
+            lru: Mutex::new(Lru::default()),
+        })
+    }
+
+    /// Keeps responses as files in `dir`, created if missing. Entries are
+    /// never evicted.
+    pub fn disk<P: Into<PathBuf>>(dir: P) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self::with_store(Store::Disk { dir }))
+    }
+
+    /// Key of a request: the path and its params with sorted keys.
+    pub fn key<U: Serialize>(path: &str, params: &U) -> Result<String> {
+        let params = normalize_body(&serde_json::to_value(params)?);
+        Ok(format!("{} {}", path, params))
+    }
+
+    fn file(dir: &Path, key: &str) -> PathBuf {
+        dir.join(format!(
+            "{}.json",
+            hex::encode(Sha256::digest(key.as_bytes()))
+        ))
+    }
+
+    /// Cached response body for `key`, counting a hit or a miss.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = match &self.store {
+            Store::Memory { lru, .. } => lru.lock().unwrap().get(key),
+            Store::Disk { dir } => fs::read_to_string(Self::file(dir, key)).ok(),
+        };
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    /// Stores a response body. Disk write errors are ignored; the response
+    /// is then simply fetched again next time.
+    pub fn put(&self, key: &str, value: String) {
+        match &self.store {
+            Store::Memory { capacity, lru } => lru.lock().unwrap().put(key, value, *capacity),
+            Store::Disk { dir } => {
+                if let Err(e) = fs::write(Self::file(dir, key), value) {
+                    log::debug!("could not write cache entry: {}", e);
+                    return;
+                }
+            }
+        }
+        self.stores.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+            entries: match &self.store {
+                Store::Memory { lru, .. } => lru.lock().unwrap().entries.len(),
+                Store::Disk { .. } => 0,
+            },
+        }
+    }
+
+    /// Highest solidified block number seen, if any.
+    pub fn solid_head(&self) -> Option<i64> {
+        Some(self.solid_head.load(Ordering::Relaxed)).filter(|head| *head >= 0)
+    }
+
+    pub(crate) fn set_solid_head(&self, number: i64) {
+        self.solid_head.fetch_max(number, Ordering::Relaxed);
+        *self.solid_head_checked.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Whether the solidified head may be refreshed now; claims the refresh
+    /// if so.
+    pub(crate) fn claim_solid_head_refresh(&self) -> bool {
+        let mut checked = self.solid_head_checked.lock().unwrap();
+        match *checked {
+            Some(at) if at.elapsed() < SOLID_HEAD_TTL => false,
+            _ => {
+                *checked = Some(Instant::now());
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = ResponseCache::memory(2);
+        cache.put("a", "1".into());
+        cache.put("b", "2".into());
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        cache.put("c", "3".into());
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c").as_deref(), Some("3"));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.stores), (2, 1, 3));
+        assert_eq!(stats.entries, 2);
+    }
+
+    #[test]
+    fn keys_on_normalized_params() {
+        let a = ResponseCache::key("/wallet/getblockbynum", &json!({"num": 1, "visible": null}));
+        let b = ResponseCache::key("/wallet/getblockbynum", &json!({"num": 1}));
+        assert_eq!(a.unwrap(), b.unwrap());
+    }
+
+    #[test]
+    fn stores_on_disk() {
+        let dir =
+            std::env::temp_dir().join(format!("tron-api-client-cache-{}", std::process::id()));
+        let cache = ResponseCache::disk(&dir).unwrap();
+        assert_eq!(cache.get("k"), None);
+        cache.put("k", "{}".into());
+        assert_eq!(
+            ResponseCache::disk(&dir).unwrap().get("k").as_deref(),
+            Some("{}")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
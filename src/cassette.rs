@@ -0,0 +1,322 @@
+//! Recording and replaying HTTP interactions.
+//!
+//! A [`CassetteTransport`] in record mode sends requests through another
+//! [`Transport`] and writes every request and response to a JSON cassette
+//! file. In replay mode it answers from the cassette without a network, so
+//! tests captured once against TronGrid run hermetically afterwards.
+//!
+//! Requests are matched on method, path with query, and JSON body. Bodies
+//! are normalized first: object keys are sorted and `null` fields dropped.
+//! Identical requests are answered in recorded order, the last response
+//! repeating. Headers are neither recorded nor matched, so API keys do not
+//! end up in cassettes.
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use tron_api_client::Client;
+//! # use tron_api_client::cassette::CassetteTransport;
+//! # use tron_api_client::transport::ReqwestTransport;
+//! // Replays the cassette; run with `TRON_RECORD=1` to record it against
+//! // the live network.
+//! let cassette = CassetteTransport::auto(
+//!     "tests/cassettes/get_now_block.json",
+//!     Arc::new(ReqwestTransport::default()),
+//! )
+//! .unwrap();
+//! let client = Client::builder()
+//!     .transport(Arc::new(cassette))
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use crate::transport::{
+    Transport, TransportError, TransportFuture, TransportRequest, TransportResponse,
+};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Environment variable making [`CassetteTransport::auto`] record.
+pub const RECORD_VAR: &str = "TRON_RECORD";
+
+/// One recorded request and its response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    /// Path and query, e.g. `/wallet/getblockbynum`.
+    pub path: String,
+    /// Normalized JSON body of the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    pub status: u16,
+    /// Response body as received.
+    pub response: String,
+}
+
+impl Interaction {
+    fn matches(&self, method: &str, path: &str, body: &Option<Value>) -> bool {
+        self.method == method && self.path == path && &self.body == body
+    }
+}
+
+/// Contents of a cassette file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Sorts object keys and drops `null` fields, recursively.
+pub fn normalize_body(body: &Value) -> Value {
+    match body {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), normalize_body(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(normalize_body).collect()),
+        value => value.clone(),
+    }
+}
+
+/// Whether a [`CassetteTransport`] records or replays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug)]
+struct State {
+    cassette: Cassette,
+    /// Interactions already replayed.
+    used: Vec<bool>,
+}
+
+/// A transport that records to or replays from a cassette file.
+pub struct CassetteTransport {
+    path: PathBuf,
+    /// Transport requests are recorded from; `None` when replaying.
+    inner: Option<Arc<dyn Transport>>,
+    state: Mutex<State>,
+}
+
+impl fmt::Debug for CassetteTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CassetteTransport")
+            .field("path", &self.path)
+            .field("mode", &self.mode())
+            .finish()
+    }
+}
+
+impl CassetteTransport {
+    /// Sends requests through `inner` and writes them to `path`, replacing
+    /// the file after every interaction.
+    pub fn record<P: Into<PathBuf>>(path: P, inner: Arc<dyn Transport>) -> Self {
+        CassetteTransport {
+            path: path.into(),
+            inner: Some(inner),
+            state: Mutex::new(State {
+                cassette: Cassette::default(),
+                used: Vec::new(),
+            }),
+        }
+    }
+
+    /// Answers from the cassette at `path`. Fails if it cannot be read.
+    pub fn replay<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let cassette = Cassette::load(&path)?;
+        let used = vec![false; cassette.interactions.len()];
+        Ok(CassetteTransport {
+            path,
+            inner: None,
+            state: Mutex::new(State { cassette, used }),
+        })
+    }
+
+    /// Replays `path`, or records it through `inner` when the
+    /// [`RECORD_VAR`] environment variable is set to anything but `0`.
+    /// Fails if the cassette is missing and recording is not requested, so
+    /// a test never reaches the network by accident.
+    pub fn auto<P: Into<PathBuf>>(path: P, inner: Arc<dyn Transport>) -> Result<Self> {
+        let path = path.into();
+        let record = env::var_os(RECORD_VAR).is_some_and(|value| value != "0");
+        if record {
+            return Ok(Self::record(path, inner));
+        }
+        if !path.exists() {
+            return Err(Error::InvalidConfig(format!(
+                "cassette {} not found, set {}=1 to record it",
+                path.display(),
+                RECORD_VAR
+            )));
+        }
+        Self::replay(path)
+    }
+
+    pub fn mode(&self) -> Mode {
+        match self.inner {
+            Some(_) => Mode::Record,
+            None => Mode::Replay,
+        }
+    }
+
+    /// Interactions recorded or loaded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.state.lock().unwrap().cassette.clone()
+    }
+
+    fn replay_request(
+        &self,
+        method: &str,
+        path: &str,
+        body: &Option<Value>,
+    ) -> std::result::Result<TransportResponse, TransportError> {
+        let mut state = self.state.lock().unwrap();
+        let State { cassette, used } = &mut *state;
+        let matching: Vec<usize> = cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.matches(method, path, body))
+            .map(|(index, _)| index)
+            .collect();
+        let index = matching
+            .iter()
+            .find(|index| !used[**index])
+            .or_else(|| matching.last())
+            .copied()
+            .ok_or_else(|| {
+                TransportError::Other(format!(
+                    "no recorded response for {} {} in {}",
+                    method,
+                    path,
+                    self.path.display()
+                ))
+            })?;
+        used[index] = true;
+        let interaction = &cassette.interactions[index];
+        Ok(TransportResponse::new(
+            interaction.status,
+            interaction.response.clone(),
+        ))
+    }
+
+    fn record_interaction(&self, interaction: Interaction) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.cassette.interactions.push(interaction);
+        state.used.push(true);
+        state.cassette.save(&self.path)
+    }
+}
+
+impl Transport for CassetteTransport {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let method = request.method.to_string();
+            let path = request.path();
+            let body = request.body.as_ref().map(normalize_body);
+
+            let inner = match &self.inner {
+                Some(inner) => inner,
+                None => return self.replay_request(&method, &path, &body),
+            };
+            let response = inner.send(request).await?;
+            self.record_interaction(Interaction {
+                method,
+                path,
+                body,
+                status: response.status,
+                response: response.body.clone(),
+            })
+            .map_err(|e| TransportError::Other(format!("could not write cassette: {}", e)))?;
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+    use reqwest::header::HeaderMap;
+    use reqwest::Method;
+    use serde_json::json;
+    use url::Url;
+
+    fn request(path: &str, body: Value) -> TransportRequest {
+        TransportRequest {
+            method: Method::POST,
+            url: Url::parse("http://node.test").unwrap().join(path).unwrap(),
+            headers: HeaderMap::new(),
+            body: Some(body),
+            timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn records_and_replays() {
+        let path = std::env::temp_dir().join(format!(
+            "tron-api-client-cassette-{}.json",
+            std::process::id()
+        ));
+        let mock = Arc::new(MockTransport::new());
+        mock.respond("POST", "/wallet/getblockbynum", 200, r#"{"n":1}"#);
+        mock.respond("POST", "/wallet/getblockbynum", 200, r#"{"n":2}"#);
+
+        let recorder = CassetteTransport::record(&path, mock);
+        let body = json!({ "num": 1, "visible": null, "a": [{ "z": 1, "b": null }] });
+        recorder
+            .send(request("/wallet/getblockbynum", body.clone()))
+            .await
+            .unwrap();
+        recorder
+            .send(request("/wallet/getblockbynum", body))
+            .await
+            .unwrap();
+        assert_eq!(
+            recorder.cassette().interactions[0].body,
+            Some(json!({ "a": [{ "z": 1 }], "num": 1 }))
+        );
+
+        let player = CassetteTransport::replay(&path).unwrap();
+        assert_eq!(player.mode(), Mode::Replay);
+        let body = json!({ "a": [{ "z": 1 }], "num": 1 });
+        let replay = |body: &Value| player.send(request("/wallet/getblockbynum", body.clone()));
+        assert_eq!(replay(&body).await.unwrap().body, r#"{"n":1}"#);
+        assert_eq!(replay(&body).await.unwrap().body, r#"{"n":2}"#);
+        assert_eq!(replay(&body).await.unwrap().body, r#"{"n":2}"#);
+        assert!(replay(&json!({ "num": 2 })).await.is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn auto_requires_a_cassette() {
+        let path = std::env::temp_dir().join("tron-api-client-missing-cassette.json");
+        let err = CassetteTransport::auto(&path, Arc::new(MockTransport::new())).unwrap_err();
+        assert!(matches!(err, Error::InvalidConfig(_)));
+    }
+}
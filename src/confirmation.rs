@@ -0,0 +1,150 @@
+//! Which confirmation level reads are answered at.
+//!
+//! A full node serves the same read endpoints under up to three prefixes:
+//! `/wallet` answers from the latest block, `/walletpbft` from the last
+//! block confirmed by PBFT consensus, and `/walletsolidity` from the last
+//! solidified block (about 19 blocks behind the head, irreversible). Not
+//! every endpoint exists under every prefix; [`Confirmation::path`] fails
+//! for those that do not.
+
+use crate::error::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Endpoints served under `/walletsolidity` and `/walletpbft`.
+const CONFIRMED: &[&str] = &[
+    "getaccount",
+    "getaccountbyid",
+    "listwitnesses",
+    "getassetissuelist",
+    "getpaginatedassetissuelist",
+    "getassetissuebyname",
+    "getassetissuebyid",
+    "getassetissuelistbyname",
+    "getnowblock",
+    "getblockbynum",
+    "getblockbyid",
+    "getblockbylimitnext",
+    "getblockbylatestnum",
+    "gettransactionbyid",
+    "gettransactioninfobyid",
+    "gettransactioncountbyblocknum",
+    "getdelegatedresource",
+    "getdelegatedresourcev2",
+    "getdelegatedresourceaccountindex",
+    "getdelegatedresourceaccountindexv2",
+    "getcandelegatedmaxsize",
+    "getavailableunfreezecount",
+    "getcanwithdrawunfreezeamount",
+    "getexchangebyid",
+    "listexchanges",
+    "triggerconstantcontract",
+    "estimateenergy",
+    "getreward",
+    "getbrokerage",
+    "getburntrx",
+];
+
+/// Endpoints served under `/walletsolidity` but not `/walletpbft`.
+const SOLIDITY_ONLY: &[&str] = &["gettransactioninfobyblocknum"];
+
+/// Confirmation level of the data a read returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Confirmation {
+    /// The latest block, which may still be reverted (`/wallet`).
+    Latest,
+    /// Confirmed by PBFT consensus (`/walletpbft`).
+    Pbft,
+    /// Solidified and irreversible (`/walletsolidity`).
+    Solidified,
+}
+
+impl Confirmation {
+    /// Path prefix of the level.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Confirmation::Latest => "/wallet",
+            Confirmation::Pbft => "/walletpbft",
+            Confirmation::Solidified => "/walletsolidity",
+        }
+    }
+
+    /// Whether `endpoint`, e.g. `getaccount`, is served at this level.
+    pub fn supports(self, endpoint: &str) -> bool {
+        match self {
+            Confirmation::Latest => true,
+            Confirmation::Pbft => CONFIRMED.contains(&endpoint),
+            Confirmation::Solidified => {
+                CONFIRMED.contains(&endpoint) || SOLIDITY_ONLY.contains(&endpoint)
+            }
+        }
+    }
+
+    /// Path of `endpoint` at this level, e.g. `/walletsolidity/getaccount`.
+    pub fn path(self, endpoint: &str) -> Result<String> {
+        if self.supports(endpoint) {
+            Ok(format!("{}/{}", self.prefix(), endpoint))
+        } else {
+            Err(Error::UnsupportedConfirmation(format!(
+                "{} has no {} variant",
+                endpoint,
+                self.prefix()
+            )))
+        }
+    }
+}
+
+impl fmt::Display for Confirmation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Confirmation::Latest => "latest",
+            Confirmation::Pbft => "pbft",
+            Confirmation::Solidified => "solidified",
+        })
+    }
+}
+
+impl FromStr for Confirmation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "latest" => Ok(Confirmation::Latest),
+            "pbft" => Ok(Confirmation::Pbft),
+            "solidified" | "solidity" => Ok(Confirmation::Solidified),
+            _ => Err(Error::UnsupportedConfirmation(format!(
+                "unknown confirmation level {}",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_to_prefix() {
+        assert_eq!(
+            Confirmation::Solidified.path("getaccount").unwrap(),
+            "/walletsolidity/getaccount"
+        );
+        assert_eq!(
+            Confirmation::Pbft.path("getblockbynum").unwrap(),
+            "/walletpbft/getblockbynum"
+        );
+        assert_eq!(
+            Confirmation::Latest.path("getaccountnet").unwrap(),
+            "/wallet/getaccountnet"
+        );
+        assert!(Confirmation::Solidified.path("getaccountnet").is_err());
+        assert!(Confirmation::Solidified
+            .path("gettransactioninfobyblocknum")
+            .is_ok());
+        assert!(Confirmation::Pbft
+            .path("gettransactioninfobyblocknum")
+            .is_err());
+        assert_eq!("PBFT".parse::<Confirmation>().unwrap(), Confirmation::Pbft);
+    }
+}
@@ -6,6 +6,8 @@ use std::fmt;
 use std::io::Error as IOError;
 
 use crate::response::Error as ResponseError;
+use crate::transport::TransportError;
+use crate::verify::Mismatch;
 
 use chrono::format::ParseError as TimeParseError;
 //# use jsonwebtoken::errors::Error as JWTError;
@@ -34,9 +36,17 @@ pub enum Error {
     /// Occurs when the provided API key is not valid.
     InvalidAPIKey,
 
-    /// Occurs when TheTVDB API returns a `5XX` error response.
+    /// Occurs when the node answers with an `{"Error": ...}` object.
     ServerError(ResponseError),
 
+    /// Occurs when the API answers with a non-2xx status that is not
+    /// covered by a more specific variant.
+    HttpStatus(StatusError),
+
+    /// Occurs when TronGrid rejects a request because the API key or the
+    /// client IP exceeded its rate limit.
+    RateLimited(StatusError),
+
     /// Occurs when resources (series, episodes, etc...) are not found.
     NotFound,
 
@@ -72,6 +82,106 @@ pub enum Error {
 
     /// Occurs when a URL cannot be parsed.
     InvalidUrl(URLParseError),
+
+    /// Occurs when a [`ClientBuilder`] setting is invalid, e.g. a malformed
+    /// header.
+    ///
+    /// [`ClientBuilder`]: ../client/struct.ClientBuilder.html
+    InvalidConfig(String),
+
+    /// Occurs when an address is not valid Base58Check or hex, or does not
+    /// carry the Tron `0x41` prefix.
+    InvalidAddress(String),
+
+    /// Occurs when a private key is malformed or out of range.
+    InvalidKey(String),
+
+    /// Occurs when producing or recovering a signature fails.
+    Signature(String),
+
+    /// Occurs when a transaction is malformed, e.g. its `txID` is not the
+    /// hash of its `raw_data`.
+    InvalidTransaction(String),
+
+    /// Occurs when protobuf data cannot be encoded or decoded.
+    Protobuf(String),
+
+    /// Occurs when a node-built transaction does not match its JSON
+    /// `raw_data` or the params it was requested with.
+    TransactionMismatch(Mismatch),
+
+    /// Occurs when a block does not match its ID, witness signature,
+    /// transactions or parent.
+    BlockMismatch(Mismatch),
+
+    /// Occurs when a keystore cannot be decrypted, e.g. because of a wrong
+    /// password, or uses an unsupported cipher or KDF.
+    Keystore(String),
+
+    /// Occurs when a mnemonic is invalid or an HD key cannot be derived.
+    KeyDerivation(String),
+
+    /// Occurs when a transaction's signatures do not fit the permissions of
+    /// its owner account.
+    Permission(String),
+
+    /// Occurs when a non-reqwest [`Transport`] fails to get a response.
+    ///
+    /// [`Transport`]: ../transport/trait.Transport.html
+    Transport(String),
+
+    /// Occurs when an endpoint is not served at the requested
+    /// [`Confirmation`] level.
+    ///
+    /// [`Confirmation`]: ../confirmation/enum.Confirmation.html
+    UnsupportedConfirmation(String),
+
+    /// Occurs when a node's genesis block is not the one of the configured
+    /// [`Network`].
+    ///
+    /// [`Network`]: ../network/enum.Network.html
+    WrongChain(String),
+}
+
+/// Bodies longer than this are cut in [`StatusError`].
+const MAX_BODY_LEN: usize = 512;
+
+/// A non-2xx response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusError {
+    pub status: u16,
+    /// Path of the request, e.g. `/wallet/getaccount`.
+    pub path: String,
+    /// Response body, truncated to 512 characters.
+    pub body: String,
+}
+
+impl StatusError {
+    pub fn new(status: u16, path: &str, body: &str) -> Self {
+        StatusError {
+            status,
+            path: path.to_string(),
+            body: truncate_body(body),
+        }
+    }
+}
+
+/// Cuts `body` to 512 characters for errors and logs.
+pub(crate) fn truncate_body(body: &str) -> String {
+    match body.char_indices().nth(MAX_BODY_LEN) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} from {}", self.status, self.path)?;
+        if !self.body.is_empty() {
+            write!(f, ": {}", self.body)?;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for Error {
@@ -84,6 +194,8 @@ impl fmt::Display for Error {
             IO(e) => write!(f, "IO error: {}", e),
             InvalidAPIKey => write!(f, "Invalid API key"),
             ServerError(e) => write!(f, "API Server error: {}", e.error),
+            HttpStatus(e) => write!(f, "HTTP status error: {}", e),
+            RateLimited(e) => write!(f, "Rate limited: {}", e),
             NotFound => write!(f, "Not found"),
             InvalidHTTPHeader(e) => write!(f, "Non-parsable HTTP header: {}", e),
             MissingLastModified => write!(f, "Last modified data missing"),
@@ -92,6 +204,20 @@ impl fmt::Display for Error {
             MissingImage => write!(f, "Image data is missing"),
             MissingSeriesSlug => write!(f, "Series slug is missing"),
             InvalidUrl(e) => write!(f, "Invalid URL: {}", e),
+            InvalidConfig(e) => write!(f, "Invalid client configuration: {}", e),
+            InvalidAddress(e) => write!(f, "Invalid address: {}", e),
+            InvalidKey(e) => write!(f, "Invalid key: {}", e),
+            Signature(e) => write!(f, "Signature error: {}", e),
+            InvalidTransaction(e) => write!(f, "Invalid transaction: {}", e),
+            Protobuf(e) => write!(f, "Protobuf error: {}", e),
+            TransactionMismatch(e) => write!(f, "Transaction mismatch: {}", e),
+            BlockMismatch(e) => write!(f, "Block mismatch: {}", e),
+            Keystore(e) => write!(f, "Keystore error: {}", e),
+            KeyDerivation(e) => write!(f, "Key derivation error: {}", e),
+            Permission(e) => write!(f, "Permission error: {}", e),
+            Transport(e) => write!(f, "Transport error: {}", e),
+            UnsupportedConfirmation(e) => write!(f, "Unsupported confirmation level: {}", e),
+            WrongChain(e) => write!(f, "Wrong chain: {}", e),
         }
     }
 }
@@ -110,10 +236,26 @@ impl StdError for Error {
             InvalidAPIKey
             | NotFound
             | ServerError(_)
+            | HttpStatus(_)
+            | RateLimited(_)
             | MissingLastModified
             | MissingSeriesFilterKeys
             | MissingImage
-            | MissingSeriesSlug => None,
+            | MissingSeriesSlug
+            | InvalidAddress(_)
+            | InvalidConfig(_)
+            | InvalidKey(_)
+            | Signature(_)
+            | InvalidTransaction(_)
+            | Protobuf(_)
+            | TransactionMismatch(_)
+            | BlockMismatch(_)
+            | Keystore(_)
+            | KeyDerivation(_)
+            | Permission(_)
+            | Transport(_)
+            | UnsupportedConfirmation(_)
+            | WrongChain(_) => None,
         }
     }
 }
@@ -130,6 +272,15 @@ impl From<ReqwestError> for Error {
     }
 }
 
+impl From<TransportError> for Error {
+    fn from(e: TransportError) -> Self {
+        match e {
+            TransportError::Http(e) => Self::HTTP(e),
+            e => Self::Transport(e.to_string()),
+        }
+    }
+}
+
 impl From<IOError> for Error {
     fn from(e: IOError) -> Self {
         Self::IO(e)
@@ -164,4 +315,13 @@ mod tests {
 
         assert_send_sync::<Error>();
     }
+
+    #[test]
+    fn truncates_status_error_body() {
+        let err = StatusError::new(502, "/wallet/getnowblock", &"é".repeat(600));
+        assert_eq!(err.body.chars().count(), MAX_BODY_LEN + 3);
+        assert!(StatusError::new(404, "/x", "")
+            .to_string()
+            .ends_with("from /x"));
+    }
 }
@@ -0,0 +1,289 @@
+//! Several full node endpoints behind one client.
+//!
+//! Requests go to a healthy endpoint; when one fails with a connection
+//! error or a 502/503/504, it is put aside for a cooldown and the request
+//! is sent to the next endpoint right away, before any retry backoff.
+//!
+//! Health comes from probes ([`Client::check_health`]) comparing the head
+//! block of every endpoint (`/wallet/getnowblock`) and, where the endpoint
+//! serves it, its solidified block (`/walletsolidity/getnowblock`). An
+//! endpoint more than `max_block_lag` blocks behind the highest head, or
+//! whose solidified block trails its own head by more than
+//! `max_solidity_lag`, is excluded until a later probe finds it in sync.
+//!
+//! [`Client::check_health`]: ../client/struct.Client.html#method.check_health
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Default number of blocks an endpoint may trail the highest head.
+pub const DEFAULT_MAX_BLOCK_LAG: u64 = 10;
+
+/// Default number of blocks the solidified block may trail the head. It
+/// normally trails by about 20 blocks.
+pub const DEFAULT_MAX_SOLIDITY_LAG: u64 = 60;
+
+/// Default time a failed endpoint is avoided.
+pub const DEFAULT_FAILURE_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// How requests are spread over healthy endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// Use endpoints in the configured order, e.g. own nodes first and
+    /// TronGrid as a fallback.
+    Priority,
+    /// Rotate over all healthy endpoints.
+    RoundRobin,
+}
+
+/// Result of probing one endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointHealth {
+    pub url: Url,
+    /// Whether requests are routed to the endpoint.
+    pub healthy: bool,
+    /// Head block number.
+    pub block: Option<i64>,
+    /// Solidified block number, if the endpoint serves `/walletsolidity`.
+    pub solidity_block: Option<i64>,
+    /// Why the endpoint is unhealthy.
+    pub reason: Option<String>,
+}
+
+/// Head and solidified block of an endpoint, or the error of the probe.
+#[derive(Debug, Clone)]
+pub(crate) struct Probe {
+    pub block: std::result::Result<i64, String>,
+    pub solidity_block: Option<i64>,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: Url,
+    healthy: AtomicBool,
+    down_until: Mutex<Option<Instant>>,
+    health: Mutex<Option<EndpointHealth>>,
+}
+
+/// The endpoints of a client and their state.
+#[derive(Debug)]
+pub(crate) struct Endpoints {
+    endpoints: Vec<Endpoint>,
+    next: AtomicUsize,
+    pub selection: Selection,
+    pub max_block_lag: u64,
+    pub max_solidity_lag: u64,
+    pub failure_cooldown: Duration,
+}
+
+impl Endpoints {
+    pub fn new(urls: Vec<Url>) -> Self {
+        Endpoints {
+            endpoints: urls
+                .into_iter()
+                .map(|url| Endpoint {
+                    url,
+                    healthy: AtomicBool::new(true),
+                    down_until: Mutex::new(None),
+                    health: Mutex::new(None),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+            selection: Selection::Priority,
+            max_block_lag: DEFAULT_MAX_BLOCK_LAG,
+            max_solidity_lag: DEFAULT_MAX_SOLIDITY_LAG,
+            failure_cooldown: DEFAULT_FAILURE_COOLDOWN,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn url(&self, index: usize) -> &Url {
+        &self.endpoints[index].url
+    }
+
+    fn is_down(&self, index: usize, now: Instant) -> bool {
+        self.endpoints[index]
+            .down_until
+            .lock()
+            .unwrap()
+            .map(|t| t > now)
+            .unwrap_or(false)
+    }
+
+    /// Index of the endpoint for the next attempt, skipping `tried`.
+    /// Prefers healthy endpoints that did not fail recently, then any that
+    /// did not fail recently, then any at all. Returns `None` once every
+    /// endpoint was tried.
+    pub fn pick(&self, tried: &[usize]) -> Option<usize> {
+        let now = Instant::now();
+        let start = match self.selection {
+            Selection::Priority => 0,
+            Selection::RoundRobin if tried.is_empty() => self.next.fetch_add(1, Ordering::Relaxed),
+            Selection::RoundRobin => self.next.load(Ordering::Relaxed).wrapping_sub(1),
+        };
+        let order: Vec<usize> = (0..self.len())
+            .map(|offset| (start + offset) % self.len())
+            .filter(|index| !tried.contains(index))
+            .collect();
+
+        let usable = |index: &&usize| !self.is_down(**index, now);
+        order
+            .iter()
+            .filter(usable)
+            .find(|index| self.endpoints[**index].healthy.load(Ordering::Relaxed))
+            .or_else(|| order.iter().find(usable))
+            .or_else(|| order.first())
+            .copied()
+    }
+
+    /// Avoids the endpoint for the failure cooldown.
+    pub fn mark_failed(&self, index: usize) {
+        *self.endpoints[index].down_until.lock().unwrap() =
+            Some(Instant::now() + self.failure_cooldown);
+    }
+
+    /// Applies the results of a probe of every endpoint.
+    pub fn update(&self, probes: Vec<Probe>) -> Vec<EndpointHealth> {
+        let health = evaluate(
+            self.endpoints.iter().map(|e| e.url.clone()).collect(),
+            probes,
+            self.max_block_lag,
+            self.max_solidity_lag,
+        );
+        for (endpoint, health) in self.endpoints.iter().zip(&health) {
+            endpoint.healthy.store(health.healthy, Ordering::Relaxed);
+            if health.healthy {
+                *endpoint.down_until.lock().unwrap() = None;
+            }
+            *endpoint.health.lock().unwrap() = Some(health.clone());
+        }
+        health
+    }
+
+    /// Last probe results; endpoints never probed are reported healthy.
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| {
+                endpoint
+                    .health
+                    .lock()
+                    .unwrap()
+                    .clone()
+                    .unwrap_or_else(|| EndpointHealth {
+                        url: endpoint.url.clone(),
+                        healthy: endpoint.healthy.load(Ordering::Relaxed),
+                        block: None,
+                        solidity_block: None,
+                        reason: None,
+                    })
+            })
+            .collect()
+    }
+}
+
+fn evaluate(
+    urls: Vec<Url>,
+    probes: Vec<Probe>,
+    max_block_lag: u64,
+    max_solidity_lag: u64,
+) -> Vec<EndpointHealth> {
+    let highest = probes
+        .iter()
+        .filter_map(|probe| probe.block.as_ref().ok())
+        .max()
+        .copied();
+
+    urls.into_iter()
+        .zip(probes)
+        .map(|(url, probe)| {
+            let block = probe.block.as_ref().ok().copied();
+            let reason = match (&probe.block, highest) {
+                (Err(e), _) => Some(format!("probe failed: {}", e)),
+                (Ok(block), Some(highest)) if (highest - block) as u64 > max_block_lag => {
+                    Some(format!(
+                        "{} blocks behind the highest head {}",
+                        highest - block,
+                        highest
+                    ))
+                }
+                (Ok(block), _) => match probe.solidity_block {
+                    Some(solid) if (block - solid).max(0) as u64 > max_solidity_lag => Some(
+                        format!("solidified block trails head by {} blocks", block - solid),
+                    ),
+                    _ => None,
+                },
+            };
+            EndpointHealth {
+                url,
+                healthy: reason.is_none(),
+                block,
+                solidity_block: probe.solidity_block,
+                reason,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints(n: usize) -> Endpoints {
+        Endpoints::new(
+            (0..n)
+                .map(|i| Url::parse(&format!("http://node{}:8090", i)).unwrap())
+                .collect(),
+        )
+    }
+
+    fn probe(block: i64, solidity_block: Option<i64>) -> Probe {
+        Probe {
+            block: Ok(block),
+            solidity_block,
+        }
+    }
+
+    #[test]
+    fn excludes_lagging_and_failed_nodes() {
+        let endpoints = endpoints(4);
+        let health = endpoints.update(vec![
+            probe(1000, Some(980)),
+            probe(985, Some(965)),
+            probe(1001, Some(900)),
+            Probe {
+                block: Err("connection refused".into()),
+                solidity_block: None,
+            },
+        ]);
+        let healthy: Vec<bool> = health.iter().map(|h| h.healthy).collect();
+        assert_eq!(healthy, vec![true, false, false, false]);
+        assert_eq!(endpoints.pick(&[]), Some(0));
+        assert_eq!(endpoints.health()[1].block, Some(985));
+    }
+
+    #[test]
+    fn fails_over_in_priority_order() {
+        let endpoints = endpoints(3);
+        assert_eq!(endpoints.pick(&[]), Some(0));
+        endpoints.mark_failed(0);
+        assert_eq!(endpoints.pick(&[]), Some(1));
+        assert_eq!(endpoints.pick(&[1]), Some(2));
+        // Failed endpoints are still used once nothing else is left.
+        assert_eq!(endpoints.pick(&[1, 2]), Some(0));
+        assert_eq!(endpoints.pick(&[0, 1, 2]), None);
+    }
+
+    #[test]
+    fn rotates_round_robin() {
+        let mut endpoints = endpoints(3);
+        endpoints.selection = Selection::RoundRobin;
+        let picks: Vec<_> = (0..4).map(|_| endpoints.pick(&[]).unwrap()).collect();
+        assert_eq!(picks, vec![0, 1, 2, 0]);
+    }
+}
@@ -0,0 +1,176 @@
+//! BIP39 mnemonics and BIP32/BIP44 hierarchical deterministic keys.
+//!
+//! Tron uses coin type 195, so account `a`, address `i` lives at
+//! `m/44'/195'/a'/0/i`. The account level extended public key
+//! (`m/44'/195'/a'`) can be handed to a [`WatchOnlyWallet`] to derive
+//! deposit addresses on a server that never sees the seed.
+
+use crate::address::Address;
+use crate::error::{Error, Result};
+use crate::signer::{public_key_to_address, PrivateKeySigner};
+use bip32::{ChildNumber, DerivationPath, Prefix, XPrv, XPub};
+use bip39::Mnemonic;
+use rand_core::{OsRng, RngCore};
+use std::fmt;
+use std::str::FromStr;
+
+/// SLIP-44 coin type of TRX.
+pub const TRON_COIN_TYPE: u32 = 195;
+
+fn err<E: fmt::Display>(e: E) -> Error {
+    Error::KeyDerivation(e.to_string())
+}
+
+/// Generates a random English mnemonic of 12, 15, 18, 21 or 24 words.
+pub fn generate_mnemonic(words: usize) -> Result<String> {
+    if ![12, 15, 18, 21, 24].contains(&words) {
+        return Err(Error::KeyDerivation(format!(
+            "invalid mnemonic length {}",
+            words
+        )));
+    }
+    let mut entropy = vec![0u8; words / 3 * 4];
+    OsRng.fill_bytes(&mut entropy);
+    Ok(Mnemonic::from_entropy(&entropy).map_err(err)?.to_string())
+}
+
+/// Checks the words and checksum of a mnemonic.
+pub fn validate_mnemonic(phrase: &str) -> Result<()> {
+    Mnemonic::parse(phrase).map(|_| ()).map_err(err)
+}
+
+/// BIP39 seed of a mnemonic and optional passphrase.
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<[u8; 64]> {
+    Ok(Mnemonic::parse(phrase).map_err(err)?.to_seed(passphrase))
+}
+
+/// `m/44'/195'/{account}'`
+pub fn account_path(account: u32) -> String {
+    format!("m/44'/{}'/{}'", TRON_COIN_TYPE, account)
+}
+
+/// `m/44'/195'/{account}'/0/{index}`
+pub fn address_path(account: u32, index: u32) -> String {
+    format!("{}/0/{}", account_path(account), index)
+}
+
+/// Private key derivation from a BIP32 root key.
+#[derive(Clone)]
+pub struct HdWallet {
+    root: XPrv,
+}
+
+impl HdWallet {
+    pub fn from_seed(seed: &[u8]) -> Result<HdWallet> {
+        Ok(HdWallet {
+            root: XPrv::new(seed).map_err(err)?,
+        })
+    }
+
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<HdWallet> {
+        HdWallet::from_seed(&mnemonic_to_seed(phrase, passphrase)?)
+    }
+
+    fn derive_xprv(&self, path: &str) -> Result<XPrv> {
+        let path = DerivationPath::from_str(path).map_err(err)?;
+        let key = path
+            .iter()
+            .try_fold(self.root.clone(), |key, child| key.derive_child(child))
+            .map_err(err)?;
+        Ok(key)
+    }
+
+    /// Derives the key at an arbitrary path, e.g. `m/44'/195'/0'/0/7`.
+    pub fn derive(&self, path: &str) -> Result<PrivateKeySigner> {
+        let key = self.derive_xprv(path)?;
+        Ok(PrivateKeySigner::from_signing_key(
+            key.private_key().clone(),
+        ))
+    }
+
+    /// Derives the key of address `index` of `account`.
+    pub fn derive_address(&self, account: u32, index: u32) -> Result<PrivateKeySigner> {
+        self.derive(&address_path(account, index))
+    }
+
+    /// Extended public key (`xpub...`) of `account`, for a
+    /// [`WatchOnlyWallet`].
+    pub fn account_xpub(&self, account: u32) -> Result<String> {
+        let key = self.derive_xprv(&account_path(account))?;
+        Ok(key.public_key().to_string(Prefix::XPUB))
+    }
+}
+
+// Never print the root key.
+impl fmt::Debug for HdWallet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HdWallet").finish()
+    }
+}
+
+/// Address derivation from an account level extended public key.
+#[derive(Debug, Clone)]
+pub struct WatchOnlyWallet {
+    account: XPub,
+}
+
+impl WatchOnlyWallet {
+    /// Takes the `xpub...` returned by [`HdWallet::account_xpub`].
+    pub fn from_xpub(xpub: &str) -> Result<WatchOnlyWallet> {
+        Ok(WatchOnlyWallet {
+            account: XPub::from_str(xpub).map_err(err)?,
+        })
+    }
+
+    /// Address `index` of the account, i.e. `<account>/0/index`.
+    pub fn address(&self, index: u32) -> Result<Address> {
+        let key = self
+            .account
+            .derive_child(ChildNumber::new(0, false).map_err(err)?)
+            .and_then(|change| change.derive_child(ChildNumber::new(index, false)?))
+            .map_err(err)?;
+        Ok(public_key_to_address(key.public_key()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::Signer;
+
+    const PHRASE: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn derives_known_address() {
+        let wallet = HdWallet::from_mnemonic(PHRASE, "").unwrap();
+        let signer = wallet.derive_address(0, 0).unwrap();
+        assert_eq!(
+            signer.address().to_base58().unwrap(),
+            "TUEZSdKsoDHQMeZwihtdoBiN46zxhGWYdH"
+        );
+    }
+
+    #[test]
+    fn watch_only_matches_private_derivation() {
+        let wallet = HdWallet::from_mnemonic(PHRASE, "").unwrap();
+        let watch = WatchOnlyWallet::from_xpub(&wallet.account_xpub(3).unwrap()).unwrap();
+        for index in 0..3 {
+            assert_eq!(
+                watch.address(index).unwrap(),
+                wallet.derive_address(3, index).unwrap().address()
+            );
+        }
+    }
+
+    #[test]
+    fn generates_valid_mnemonics() {
+        for words in &[12, 24] {
+            let phrase = generate_mnemonic(*words).unwrap();
+            assert_eq!(phrase.split(' ').count(), *words);
+            validate_mnemonic(&phrase).unwrap();
+        }
+        assert!(generate_mnemonic(13).is_err());
+        assert!(validate_mnemonic(&PHRASE.replace("about", "abandon")).is_err());
+    }
+}
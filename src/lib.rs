@@ -1,13 +1,36 @@
 // ...
 //
+pub mod address;
+pub mod api_key;
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+pub mod cassette;
 pub mod client;
+pub mod confirmation;
 pub mod error;
+pub mod failover;
+pub mod hd;
+pub mod message;
+pub mod multisig;
+pub mod network;
 pub mod params;
+pub mod proto;
+pub mod rate_limit;
 pub mod response;
+pub mod retry;
+pub mod signer;
+pub mod telemetry;
+pub mod transport;
+pub mod verify;
+pub mod wallet;
 
 pub use client::Address;
 pub use client::Client;
 pub use client::TxId;
+pub use confirmation::Confirmation;
+pub use signer::{PrivateKeySigner, Signer};
 
 // ...
 // ...
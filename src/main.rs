@@ -1,7 +1,11 @@
 use clap::*;
+use std::env;
+use std::io::{self, BufRead, IsTerminal};
 use std::process;
 
 use serde_json::json;
+use tron_api_client::network::{Network, NetworkConfig};
+use tron_api_client::wallet::{self, Keystore, Kdf};
 use tron_api_client::*;
 
 // pub async fn get_node_info(&self) -> Result<NodeInfo> {
@@ -26,8 +30,6 @@ use tron_api_client::*;
 // get_transaction_info_by_id tx_id: TxId
 // get_contract address: Address
 
-// TODO: support base58 addresses
-
 #[tokio::main]
 async fn main() {
     let app = clap_app!(myapp =>
@@ -38,11 +40,14 @@ async fn main() {
         (@arg network:
             --network
              default_value("main")
-             possible_value[main]
-             possible_value[shasta]
              env("TRON_NETWORK")
             +takes_value
-            "Specify tron network (uses trongrid.io)")
+            "Specify tron network: main, shasta, nile (uses trongrid.io) or one from --networks")
+        (@arg networks:
+            --networks
+             env("TRON_NETWORKS")
+            +takes_value
+            "JSON file with custom network definitions")
         (@arg api_url:
             --api
             env("TRON_API")
@@ -79,11 +84,11 @@ async fn main() {
         )
         (@subcommand get_account =>
             (about: "Get Account")
-            (@arg address: +required "Address (hex format)")
+            (@arg address: +required "Address (base58 or hex format)")
         )
         (@subcommand get_account_net =>
             (about: "Get Account Bandwidth")
-            (@arg address: +required "Address (hex format)")
+            (@arg address: +required "Address (base58 or hex format)")
         )
         (@subcommand get_transaction_by_id =>
             (about: "Get Transaction by Id")
@@ -95,7 +100,7 @@ async fn main() {
         )
         (@subcommand get_contract =>
             (about: "Get Contract")
-            (@arg address: +required "Contract Address (hex format)")
+            (@arg address: +required "Contract Address (base58 or hex format)")
         )
         (@subcommand list_witnesses =>
             (about: "List Witnesses")
@@ -103,6 +108,25 @@ async fn main() {
         (@subcommand get_asset_issue_list =>
             (about: "List TRC10 Tokens")
         )
+        (@subcommand new_keystore =>
+            (about: "Generate a new key and save it to an encrypted keystore file")
+            (after_help: SECRETS_HELP)
+            (@arg file: +required "Keystore file to create")
+            (@arg password_env: --("password-env") +takes_value "Read the keystore password from this environment variable")
+        )
+        (@subcommand import_key =>
+            (about: "Encrypt an existing private key into a keystore file")
+            (after_help: SECRETS_HELP)
+            (@arg file: +required "Keystore file to create")
+            (@arg key_env: --("key-env") +takes_value "Read the private key (hex format) from this environment variable")
+            (@arg password_env: --("password-env") +takes_value "Read the keystore password from this environment variable")
+        )
+        (@subcommand keystore_address =>
+            (about: "Decrypt a keystore file and print its address")
+            (after_help: SECRETS_HELP)
+            (@arg file: +required "Keystore file")
+            (@arg password_env: --("password-env") +takes_value "Read the keystore password from this environment variable")
+        )
     )
     .setting(clap::AppSettings::SubcommandRequiredElseHelp);
 
@@ -110,16 +134,26 @@ async fn main() {
 
     let (command_name, submatches) = matches.subcommand();
 
-    // TODO: configurable!
-    let client = match (
+    let builder = match (
         matches.value_of("api_url"),
         matches.value_of("network").unwrap(),
     ) {
-        (None, "main") => Client::for_main(None),
-        (None, "shasta") => Client::for_shasta(None),
-        (Some(api_url), _) => Client::new(api_url.to_string(), None),
-        (_, _) => unimplemented!(),
+        (Some(api_url), _) => Client::builder().base_url(api_url),
+        (None, network) => {
+            let custom = match matches.value_of("networks") {
+                Some(path) => NetworkConfig::load(path)
+                    .unwrap_or_else(|e| die(&format!("could not load networks: {}", e))),
+                None => Vec::new(),
+            };
+            Client::builder().network(
+                Network::parse_with(network, &custom)
+                    .unwrap_or_else(|_| die(&format!("unknown network {}", network))),
+            )
+        }
     };
+    let client = builder
+        .build()
+        .unwrap_or_else(|e| die(&format!("could not create client: {}", e)));
 
     match command_name {
         "get_node_info" => {
@@ -170,7 +204,9 @@ async fn main() {
             let submatches = submatches.unwrap();
             let address: String =
                 value_t!(submatches, "address", String).unwrap_or_else(|e| e.exit());
-            let address = Address::Hex(address);
+            let address: Address = address
+                .parse()
+                .unwrap_or_else(|e| die(&format!("{}", e)));
             let res = client.get_account(address).await.unwrap();
             println!("{}", serde_json::to_string_pretty(&res).unwrap());
         }
@@ -178,7 +214,9 @@ async fn main() {
             let submatches = submatches.unwrap();
             let address: String =
                 value_t!(submatches, "address", String).unwrap_or_else(|e| e.exit());
-            let address = Address::Hex(address);
+            let address: Address = address
+                .parse()
+                .unwrap_or_else(|e| die(&format!("{}", e)));
             let res = client.get_account_net(address).await.unwrap();
             println!("{}", serde_json::to_string_pretty(&res).unwrap());
         }
@@ -200,7 +238,9 @@ async fn main() {
             let submatches = submatches.unwrap();
             let address: String =
                 value_t!(submatches, "address", String).unwrap_or_else(|e| e.exit());
-            let address = Address::Hex(address);
+            let address: Address = address
+                .parse()
+                .unwrap_or_else(|e| die(&format!("{}", e)));
             let res = client.get_contract(address).await.unwrap();
             println!("{}", serde_json::to_string_pretty(&res).unwrap());
         }
@@ -214,6 +254,34 @@ async fn main() {
             let res = client.get_asset_issue_list().await.unwrap();
             println!("{}", serde_json::to_string_pretty(&res).unwrap());
         }
+        "new_keystore" | "import_key" | "keystore_address" => {
+            let submatches = submatches.unwrap();
+            let file = submatches.value_of("file").unwrap();
+            let signer = match command_name {
+                "new_keystore" => wallet::generate(),
+                "import_key" => {
+                    let key = secret(submatches.value_of("key_env"), "Private key", false);
+                    PrivateKeySigner::from_hex(&key).unwrap_or_else(|e| die(&format!("{}", e)))
+                }
+                _ => {
+                    let password = secret(submatches.value_of("password_env"), "Password", false);
+                    wallet::read_keystore(file, &password)
+                        .unwrap_or_else(|e| die(&format!("{}", e)))
+                }
+            };
+            if command_name != "keystore_address" {
+                let password = secret(submatches.value_of("password_env"), "Password", true);
+                Keystore::encrypt(&signer, &password, Kdf::scrypt())
+                    .and_then(|keystore| keystore.save(file))
+                    .unwrap_or_else(|e| die(&format!("{}", e)));
+            }
+            let address = signer.address();
+            let res = json!({
+                "address": address.to_base58().unwrap(),
+                "hex": address.to_hex().unwrap(),
+            });
+            println!("{}", serde_json::to_string_pretty(&res).unwrap());
+        }
         _ => unimplemented!(),
     }
 }
@@ -230,6 +298,89 @@ fn validate_hex(hex: String) -> std::result::Result<(), String> {
 }
 */
 
+const SECRETS_HELP: &str = "Private keys and passwords are never taken as arguments. They are \
+read from the terminal without echo, or one per line from piped stdin (key first), unless \
+--key-env/--password-env name an environment variable to read them from.";
+
+/// Reads a secret from the environment variable `env` if given, else from
+/// stdin. On a terminal, input is not echoed and new secrets are asked for
+/// twice.
+fn secret(env: Option<&str>, prompt: &str, confirm: bool) -> String {
+    if let Some(var) = env {
+        return env::var(var).unwrap_or_else(|_| die(&format!("{} is not set", var)));
+    }
+    let value = read_secret(prompt);
+    let repeat = format!("Repeat {}", prompt.to_lowercase());
+    if confirm && io::stdin().is_terminal() && read_secret(&repeat) != value {
+        die(&format!("{}s do not match", prompt.to_lowercase()));
+    }
+    value
+}
+
+fn read_secret(prompt: &str) -> String {
+    let stdin = io::stdin();
+    let tty = stdin.is_terminal();
+    if tty {
+        eprint!("{}: ", prompt);
+    }
+    let echo = if tty { EchoOff::new() } else { None };
+    let mut line = String::new();
+    let read = stdin.lock().read_line(&mut line);
+    drop(echo);
+    if tty {
+        eprintln!();
+    }
+    match read {
+        Ok(0) => die(&format!("{} missing on stdin", prompt)),
+        Ok(_) => line.trim_end_matches(&['\r', '\n'][..]).to_string(),
+        Err(e) => die(&format!("could not read {}: {}", prompt.to_lowercase(), e)),
+    }
+}
+
+/// Turns terminal echo off until dropped.
+#[cfg(unix)]
+struct EchoOff(libc::termios);
+
+#[cfg(unix)]
+impl EchoOff {
+    fn new() -> Option<Self> {
+        // Safety: tcgetattr and tcsetattr only access the termios struct
+        // passed to them.
+        unsafe {
+            let mut term: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut term) != 0 {
+                return None;
+            }
+            let saved = term;
+            term.c_lflag &= !libc::ECHO;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &term) != 0 {
+                return None;
+            }
+            Some(EchoOff(saved))
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        // Safety: restores the settings read in `new`.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct EchoOff;
+
+#[cfg(not(unix))]
+impl EchoOff {
+    fn new() -> Option<Self> {
+        None
+    }
+}
+
 fn die(msg: &str) -> ! {
     eprintln!("{}", msg);
     process::exit(1);
@@ -0,0 +1,193 @@
+//! TIP-191 message signing.
+//!
+//! Messages are signed over
+//! `keccak256("\x19TRON Signed Message:\n" || len(message) || message)`,
+//! so that a signed message can never be mistaken for a transaction. This is
+//! what TronWeb's `signMessageV2` / `verifyMessageV2` do; signatures are
+//! `0x` prefixed hex `r || s || v` strings with `v` 27 or 28.
+//!
+//! The legacy `signString` / `verifyMessage` (v1) scheme is supported too:
+//! it takes a hex encoded message (usually a 32 byte hash) and always
+//! writes `32` as the length, whatever the message size.
+
+use crate::address::Address;
+use crate::error::{Error, Result};
+use crate::signer::{recover_address, Signer};
+use sha3::{Digest, Keccak256};
+
+/// Prefix of TIP-191 signed messages.
+pub const TRON_MESSAGE_PREFIX: &str = "\x19TRON Signed Message:\n";
+
+/// Prefix of EIP-191 signed messages, accepted by the legacy v1 scheme.
+pub const ETH_MESSAGE_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+
+fn hash_with_prefix(prefix: &str, length: &str, message: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(prefix.as_bytes());
+    hasher.update(length.as_bytes());
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+fn encode_signature(signature: &[u8]) -> String {
+    format!("0x{}", hex::encode(signature))
+}
+
+fn decode_signature(signature: &str) -> Result<Vec<u8>> {
+    let signature = signature.trim_start_matches("0x");
+    hex::decode(signature).map_err(|e| Error::Signature(format!("signature: {}", e)))
+}
+
+fn same_address(a: &Address, b: &Address) -> Result<bool> {
+    Ok(a.to_bytes()? == b.to_bytes()?)
+}
+
+/// TIP-191 hash of `message`. Strings are hashed as their UTF-8 bytes.
+pub fn hash_message<M: AsRef<[u8]>>(message: M) -> [u8; 32] {
+    let message = message.as_ref();
+    hash_with_prefix(TRON_MESSAGE_PREFIX, &message.len().to_string(), message)
+}
+
+/// Signs `message` like TronWeb's `signMessageV2`.
+pub fn sign_message<S: Signer + ?Sized, M: AsRef<[u8]>>(signer: &S, message: M) -> Result<String> {
+    Ok(encode_signature(&signer.sign_hash(&hash_message(message))?))
+}
+
+/// Recovers the signer of a message like TronWeb's `verifyMessageV2`.
+pub fn recover_message_signer<M: AsRef<[u8]>>(message: M, signature: &str) -> Result<Address> {
+    recover_address(&hash_message(message), &decode_signature(signature)?)
+}
+
+/// Whether `signature` is a signature of `message` by `address`.
+pub fn verify_message<M: AsRef<[u8]>>(
+    message: M,
+    signature: &str,
+    address: &Address,
+) -> Result<bool> {
+    same_address(&recover_message_signer(message, signature)?, address)
+}
+
+/// Legacy (v1) hash of a hex encoded message, with the TRON or, when
+/// `use_tron_header` is false, the Ethereum prefix.
+pub fn hash_message_v1(hex_message: &str, use_tron_header: bool) -> Result<[u8; 32]> {
+    let message = hex::decode(hex_message.trim_start_matches("0x"))
+        .map_err(|e| Error::Signature(format!("message: {}", e)))?;
+    let prefix = if use_tron_header {
+        TRON_MESSAGE_PREFIX
+    } else {
+        ETH_MESSAGE_PREFIX
+    };
+    Ok(hash_with_prefix(prefix, "32", &message))
+}
+
+/// Signs a hex encoded message like TronWeb's legacy `signString`.
+pub fn sign_message_v1<S: Signer + ?Sized>(
+    signer: &S,
+    hex_message: &str,
+    use_tron_header: bool,
+) -> Result<String> {
+    let hash = hash_message_v1(hex_message, use_tron_header)?;
+    Ok(encode_signature(&signer.sign_hash(&hash)?))
+}
+
+/// Checks a legacy signature like TronWeb's `verifyMessage`.
+pub fn verify_message_v1(
+    hex_message: &str,
+    signature: &str,
+    address: &Address,
+    use_tron_header: bool,
+) -> Result<bool> {
+    let hash = hash_message_v1(hex_message, use_tron_header)?;
+    let recovered = recover_address(&hash, &decode_signature(signature)?)?;
+    same_address(&recovered, address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::PrivateKeySigner;
+
+    fn signer() -> PrivateKeySigner {
+        PrivateKeySigner::from_bytes(&[7u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn hashes_like_eip191() {
+        // ethers.utils.hashMessage("Hello World")
+        assert_eq!(
+            hex::encode(hash_with_prefix(ETH_MESSAGE_PREFIX, "11", b"Hello World")),
+            "a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2"
+        );
+    }
+
+    /// Private key of the web3.js `accounts.sign` documentation example.
+    const KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn matches_known_signatures() {
+        let signer = PrivateKeySigner::from_hex(KEY).unwrap();
+        assert_eq!(
+            signer.address().to_base58().unwrap(),
+            "TE2H9hWjzYdwzDFRJfx9BFhr4MmjH1CHaz"
+        );
+
+        // web3.eth.accounts.sign("Some data", KEY)
+        let hash = hash_with_prefix(ETH_MESSAGE_PREFIX, "9", b"Some data");
+        assert_eq!(
+            encode_signature(&signer.sign_hash(&hash).unwrap()),
+            "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd\
+             6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"
+        );
+
+        // tronWeb.trx.signMessageV2("hello tron", KEY), i.e. ethers'
+        // SigningKey (RFC 6979 nonces, low s) over the TIP-191 hash.
+        let signature = "0x55c1f45e7ab18c989fd361c9eb46562e4e0838b7121e3ff1ee0f9314dea81e65\
+                         649a94a0d9e48e95706e127fba6b79db89eaa46cb993d9a1213951f82619284f1b";
+        assert_eq!(
+            hex::encode(hash_message("hello tron")),
+            "85fdc81d273d14847afd4ef8b63674e9c9b9a285f00c4fcc792e541a98d25c16"
+        );
+        assert_eq!(sign_message(&signer, "hello tron").unwrap(), signature);
+        assert!(verify_message("hello tron", signature, &signer.address()).unwrap());
+
+        // tronWeb.trx.signString(TX_ID, KEY, true), hashed with the
+        // "\x19TRON Signed Message:\n32" header.
+        let tx_id = "809e9d9aa5381f32f748618e4d592a58542e21fe794f35959ce811f2a58fc969";
+        let signature = "0xc8a2e8f5766f7085809adc553ffd36ddbfe4e5cc18dcaf5c1f3c42860cdf6119\
+                         43f46f2a2f78dc972879ea8b8b4eb2f955c7cfc4a29b853f20410cbf2e1fe9281b";
+        assert_eq!(
+            hex::encode(hash_message_v1(tx_id, true).unwrap()),
+            "5fb8c4b874052464e842cdc216d1cdf8fe3ad83c623a86fcebfa75ee2bad1daa"
+        );
+        assert_eq!(sign_message_v1(&signer, tx_id, true).unwrap(), signature);
+        assert!(verify_message_v1(tx_id, signature, &signer.address(), true).unwrap());
+    }
+
+    #[test]
+    fn signs_and_verifies_messages() {
+        let signer = signer();
+        let signature = sign_message(&signer, "hello tron").unwrap();
+        assert!(signature.starts_with("0x"));
+        assert_eq!(signature.len(), 2 + 130);
+        assert!(signature.ends_with("1b") || signature.ends_with("1c"));
+
+        let address: Address = signer.address().to_base58().unwrap().parse().unwrap();
+        assert!(verify_message("hello tron", &signature, &address).unwrap());
+        assert!(!verify_message("hello tron!", &signature, &address).unwrap());
+        assert_eq!(
+            recover_message_signer(b"hello tron", &signature).unwrap(),
+            signer.address()
+        );
+    }
+
+    #[test]
+    fn signs_and_verifies_v1_messages() {
+        let signer = signer();
+        let message = "0x".to_string() + &"ab".repeat(32);
+        for &tron in &[true, false] {
+            let signature = sign_message_v1(&signer, &message, tron).unwrap();
+            assert!(verify_message_v1(&message, &signature, &signer.address(), tron).unwrap());
+            assert!(!verify_message_v1(&message, &signature, &signer.address(), !tron).unwrap());
+        }
+    }
+}
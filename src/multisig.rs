@@ -0,0 +1,296 @@
+//! Local multi-signature weight checks.
+//!
+//! Mirrors what `/wallet/getsignweight` does: recover every signer of a
+//! transaction, look up the permission selected by the contract's
+//! `Permission_id` in the owner's [`Account`], and sum the weights of the
+//! signers listed in it.
+
+use crate::address::Address;
+use crate::error::{Error, Result};
+use crate::proto;
+use crate::response::account::{Account, Permission, PermissionKey};
+use crate::response::Transaction;
+use crate::signer::recover_signers;
+
+/// Permission id of the owner permission.
+pub const OWNER_PERMISSION_ID: i32 = 0;
+
+/// Permission id of the witness permission.
+pub const WITNESS_PERMISSION_ID: i32 = 1;
+
+fn err<S: Into<String>>(msg: S) -> Error {
+    Error::Permission(msg.into())
+}
+
+/// Result of [`check_sign_weight`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignWeight {
+    /// The permission the transaction is signed under.
+    pub permission: Permission,
+    /// Recovered signers, in signature order.
+    pub approved: Vec<Address>,
+    /// Sum of the weights of `approved`.
+    pub current_weight: i64,
+}
+
+impl SignWeight {
+    /// Whether enough weight has signed for the transaction to be accepted.
+    pub fn is_ready(&self) -> bool {
+        self.current_weight >= self.permission.threshold
+    }
+
+    /// Weight still missing to reach the threshold.
+    pub fn missing_weight(&self) -> i64 {
+        (self.permission.threshold - self.current_weight).max(0)
+    }
+}
+
+/// Returns the permission with the given id. Accounts that never updated
+/// their permissions have no `owner_permission`; their owner permission is
+/// the account key alone with threshold 1.
+pub fn find_permission(account: &Account, permission_id: i32) -> Result<Permission> {
+    let found = match permission_id {
+        OWNER_PERMISSION_ID => {
+            Some(
+                account
+                    .owner_permission
+                    .clone()
+                    .unwrap_or_else(|| Permission {
+                        permission_name: "owner".into(),
+                        threshold: 1,
+                        keys: vec![PermissionKey {
+                            address: account.address.clone(),
+                            weight: 1,
+                        }],
+                        ..Default::default()
+                    }),
+            )
+        }
+        WITNESS_PERMISSION_ID => account.witness_permission.clone(),
+        id => account
+            .active_permission
+            .iter()
+            .find(|p| p.id == id)
+            .cloned(),
+    };
+    found.ok_or_else(|| err(format!("account has no permission {}", permission_id)))
+}
+
+/// Whether an active permission's `operations` bitmask allows
+/// `contract_type`. Owner and witness permissions allow everything.
+pub fn permission_allows(permission: &Permission, contract_type: &str) -> Result<bool> {
+    let operations = match &permission.operations {
+        Some(operations) if permission.id > WITNESS_PERMISSION_ID => operations,
+        _ => return Ok(true),
+    };
+    let operations = hex::decode(operations).map_err(|e| err(format!("operations: {}", e)))?;
+    let bit = proto::contract_type_number(contract_type)? as usize;
+    Ok(operations
+        .get(bit / 8)
+        .map(|byte| byte & (1 << (bit % 8)) != 0)
+        .unwrap_or(false))
+}
+
+fn same_address(a: &str, b: &Address) -> Result<bool> {
+    Ok(a.parse::<Address>()?.to_bytes()? == b.to_bytes()?)
+}
+
+/// Checks the signatures of `tx` against the permissions of `account` (the
+/// owner of the transaction, as returned by `Client::get_account`).
+///
+/// The owner, contract type and `Permission_id` are read from the signed
+/// `raw_data_hex`, not from the JSON `raw_data`, which a node could make
+/// disagree with it.
+///
+/// Fails like the node would if a signer is not part of the permission,
+/// signs twice, or the permission does not allow the contract type.
+pub fn check_sign_weight(tx: &Transaction, account: &Account) -> Result<SignWeight> {
+    let raw_data = tx.decode_raw_data()?;
+    let contract = raw_data
+        .contract
+        .first()
+        .ok_or_else(|| err("transaction has no contract"))?;
+    if !same_address(
+        &contract.parameter.value.owner_address,
+        &account.address.parse()?,
+    )? {
+        return Err(err(format!(
+            "transaction owner {} is not account {}",
+            contract.parameter.value.owner_address, account.address
+        )));
+    }
+
+    let permission = find_permission(account, contract.permission_id.unwrap_or_default())?;
+    if !permission_allows(&permission, &contract.type_field)? {
+        return Err(err(format!(
+            "permission {} does not allow {}",
+            permission.id, contract.type_field
+        )));
+    }
+
+    let approved = recover_signers(tx)?;
+    let mut current_weight = 0;
+    for (i, signer) in approved.iter().enumerate() {
+        if approved[..i].contains(signer) {
+            return Err(err(format!("{} signed more than once", signer)));
+        }
+        let mut weight = None;
+        for key in &permission.keys {
+            if same_address(&key.address, signer)? {
+                weight = Some(key.weight);
+                break;
+            }
+        }
+        current_weight += weight.ok_or_else(|| {
+            err(format!(
+                "{} is not a key of permission {}",
+                signer, permission.id
+            ))
+        })?;
+    }
+
+    Ok(SignWeight {
+        permission,
+        approved,
+        current_weight,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::transaction::{Contract, Parameter, RawData, Value};
+    use crate::signer::{PrivateKeySigner, Signer};
+
+    fn signer(n: u8) -> PrivateKeySigner {
+        let mut key = [0u8; 32];
+        key[31] = n;
+        PrivateKeySigner::from_bytes(&key).unwrap()
+    }
+
+    fn transfer(owner: &Address, permission_id: Option<i32>) -> Transaction {
+        let raw = RawData {
+            contract: vec![Contract {
+                parameter: Parameter {
+                    value: Value {
+                        owner_address: owner.to_hex().unwrap(),
+                        to_address: Some(signer(9).address().to_hex().unwrap()),
+                        amount: Some(1),
+                        ..Default::default()
+                    },
+                    type_url: "type.googleapis.com/protocol.TransferContract".into(),
+                },
+                type_field: "TransferContract".into(),
+                permission_id,
+            }],
+            ref_block_bytes: "1098".into(),
+            ref_block_hash: "0b5d4831c80cfdae".into(),
+            expiration: 1585881453000,
+            timestamp: 1585881393538,
+            ..Default::default()
+        };
+        Transaction::from_raw_data(raw, false).unwrap()
+    }
+
+    fn multisig_account(owner: &Address) -> Account {
+        let key = |n: u8, weight: i64| PermissionKey {
+            address: signer(n).address().to_hex().unwrap(),
+            weight,
+        };
+        Account {
+            address: owner.to_hex().unwrap(),
+            owner_permission: Some(Permission {
+                permission_name: "owner".into(),
+                threshold: 1,
+                keys: vec![key(1, 1)],
+                ..Default::default()
+            }),
+            active_permission: vec![Permission {
+                type_field: Some("Active".into()),
+                id: 2,
+                permission_name: "active".into(),
+                threshold: 3,
+                // TransferContract (1) only
+                operations: Some(format!("02{}", "00".repeat(31))),
+                keys: vec![key(2, 2), key(3, 1), key(4, 1)],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accumulates_weight() {
+        let owner = signer(1).address();
+        let account = multisig_account(&owner);
+        let mut tx = transfer(&owner, Some(2));
+
+        signer(2).sign_transaction(&mut tx).unwrap();
+        let weight = check_sign_weight(&tx, &account).unwrap();
+        assert_eq!(weight.current_weight, 2);
+        assert!(!weight.is_ready());
+        assert_eq!(weight.missing_weight(), 1);
+
+        signer(4).sign_transaction(&mut tx).unwrap();
+        let weight = check_sign_weight(&tx, &account).unwrap();
+        assert!(weight.is_ready());
+        assert_eq!(
+            weight.approved,
+            vec![signer(2).address(), signer(4).address()]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_duplicate_signers() {
+        let owner = signer(1).address();
+        let account = multisig_account(&owner);
+
+        let mut tx = transfer(&owner, Some(2));
+        signer(1).sign_transaction(&mut tx).unwrap();
+        assert!(check_sign_weight(&tx, &account).is_err());
+
+        let mut tx = transfer(&owner, Some(2));
+        signer(3).sign_transaction(&mut tx).unwrap();
+        signer(3).sign_transaction(&mut tx).unwrap();
+        assert!(check_sign_weight(&tx, &account).is_err());
+    }
+
+    #[test]
+    fn reads_permission_from_raw_data_hex() {
+        let owner = signer(1).address();
+        let account = multisig_account(&owner);
+        let mut tx = transfer(&owner, Some(2));
+        signer(2).sign_transaction(&mut tx).unwrap();
+        signer(3).sign_transaction(&mut tx).unwrap();
+
+        // A JSON view pointing at the owner permission, which signers 2 and
+        // 3 are not part of, does not change the signed permission.
+        tx.raw_data.contract[0].permission_id = None;
+        let weight = check_sign_weight(&tx, &account).unwrap();
+        assert_eq!(weight.permission.id, 2);
+        assert!(weight.is_ready());
+    }
+
+    #[test]
+    fn defaults_to_owner_key() {
+        let owner = signer(1).address();
+        let account = Account {
+            address: owner.to_hex().unwrap(),
+            ..Default::default()
+        };
+        let mut tx = transfer(&owner, None);
+        signer(1).sign_transaction(&mut tx).unwrap();
+        assert!(check_sign_weight(&tx, &account).unwrap().is_ready());
+    }
+
+    #[test]
+    fn checks_operations() {
+        let permission = Permission {
+            id: 2,
+            operations: Some(format!("02{}", "00".repeat(31))),
+            ..Default::default()
+        };
+        assert!(permission_allows(&permission, "TransferContract").unwrap());
+        assert!(!permission_allows(&permission, "TriggerSmartContract").unwrap());
+    }
+}
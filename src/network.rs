@@ -0,0 +1,206 @@
+//! Networks a client can connect to.
+//!
+//! Besides the public TronGrid networks, a [`Network`] can describe any
+//! chain, e.g. a private java-tron network, through a [`NetworkConfig`]:
+//! the full node URL, optional separate URLs for the solidity node, the
+//! PBFT API and the event API, and the ID of the genesis block. Configs
+//! are plain serde structs and can be loaded from a JSON file:
+//!
+//! ```json
+//! [
+//!   {
+//!     "name": "private",
+//!     "fullnode_url": "http://10.0.0.5:8090",
+//!     "solidity_url": "http://10.0.0.5:8091",
+//!     "pbft_url": "http://10.0.0.5:8092",
+//!     "genesis_block_id": "0000000000000000a2c0a7d1c1b61ab6e39b2e1b8d0f5a3e1c5b4f8d9e7a6b5c"
+//!   }
+//! ]
+//! ```
+//!
+//! With a genesis block ID set, [`Client::verify_chain`] checks that the
+//! node serves that chain.
+//!
+//! [`Client::verify_chain`]: ../client/struct.Client.html#method.verify_chain
+
+use crate::error::{Error, Result};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const MAIN_GENESIS: &str = "00000000000000001ebf88508a03865c71d452e25f4d51194196a1d22b6653dc";
+const SHASTA_GENESIS: &str = "0000000000000000de1aa88295e1fcf982742f773e0419c5a9c134c994a9059e";
+const NILE_GENESIS: &str = "0000000000000000d698d4192c56cb6be724a558448e2684802de4d6cd8690dc";
+
+/// Endpoints and identity of a chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Display name, also used to look the network up by name.
+    pub name: String,
+    /// Full node HTTP API, serving `/wallet`.
+    pub fullnode_url: String,
+    /// Solidity node serving `/walletsolidity`, if not the full node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solidity_url: Option<String>,
+    /// Node serving `/walletpbft`, if not the full node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pbft_url: Option<String>,
+    /// Event API serving `/v1`, if not the full node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_url: Option<String>,
+    /// `blockID` of block 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis_block_id: Option<String>,
+}
+
+impl NetworkConfig {
+    pub fn new<N: Into<String>, U: Into<String>>(name: N, fullnode_url: U) -> Self {
+        NetworkConfig {
+            name: name.into(),
+            fullnode_url: fullnode_url.into(),
+            solidity_url: None,
+            pbft_url: None,
+            event_url: None,
+            genesis_block_id: None,
+        }
+    }
+
+    /// Reads a JSON file holding one config or an array of them.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<NetworkConfig>> {
+        let data = fs::read_to_string(path)?;
+        Self::parse_list(&data)
+    }
+
+    /// Parses JSON holding one config or an array of them.
+    pub fn parse_list(data: &str) -> Result<Vec<NetworkConfig>> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrMany {
+            One(NetworkConfig),
+            Many(Vec<NetworkConfig>),
+        }
+
+        Ok(match serde_json::from_str(data)? {
+            OneOrMany::One(config) => vec![config],
+            OneOrMany::Many(configs) => configs,
+        })
+    }
+}
+
+/// A TronGrid network or a custom one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Network {
+    Main,
+    Shasta,
+    Nile,
+    Custom(Box<NetworkConfig>),
+}
+
+impl Network {
+    /// Full node URL of the network; the TronGrid URL for public networks.
+    pub fn base_url(&self) -> &str {
+        match self {
+            Network::Shasta => "https://api.shasta.trongrid.io",
+            Network::Main => "https://api.trongrid.io",
+            Network::Nile => "https://api.nile.trongrid.io",
+            Network::Custom(config) => &config.fullnode_url,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Network::Main => "main",
+            Network::Shasta => "shasta",
+            Network::Nile => "nile",
+            Network::Custom(config) => &config.name,
+        }
+    }
+
+    /// ID of block 0, if known.
+    pub fn genesis_block_id(&self) -> Option<&str> {
+        match self {
+            Network::Main => Some(MAIN_GENESIS),
+            Network::Shasta => Some(SHASTA_GENESIS),
+            Network::Nile => Some(NILE_GENESIS),
+            Network::Custom(config) => config.genesis_block_id.as_deref(),
+        }
+    }
+
+    /// The network as a config; TronGrid serves every API under one URL.
+    pub fn config(&self) -> NetworkConfig {
+        match self {
+            Network::Custom(config) => (**config).clone(),
+            network => NetworkConfig {
+                genesis_block_id: network.genesis_block_id().map(String::from),
+                ..NetworkConfig::new(network.name(), network.base_url())
+            },
+        }
+    }
+
+    /// Looks `name` up among the public networks, then among `custom`.
+    pub fn parse_with(name: &str, custom: &[NetworkConfig]) -> Result<Self> {
+        name.parse().or_else(|e| {
+            custom
+                .iter()
+                .find(|config| config.name.eq_ignore_ascii_case(name))
+                .map(|config| Network::Custom(Box::new(config.clone())))
+                .ok_or(e)
+        })
+    }
+}
+
+impl From<NetworkConfig> for Network {
+    fn from(config: NetworkConfig) -> Self {
+        Network::Custom(Box::new(config))
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Network {
+    type Err = Error;
+
+    /// Parses the name of a public network.
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "main" | "mainnet" => Self::Main,
+            "shasta" => Self::Shasta,
+            "nile" => Self::Nile,
+            _ => return Err(Error::InvalidConfig(format!("unknown network {}", s))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_and_configs() {
+        assert_eq!("Shasta".parse::<Network>().unwrap(), Network::Shasta);
+        assert!("private".parse::<Network>().is_err());
+
+        let configs = NetworkConfig::parse_list(
+            r#"{"name": "private", "fullnode_url": "http://10.0.0.5:8090", "solidity_url": "http://10.0.0.5:8091"}"#,
+        )
+        .unwrap();
+        let network = Network::parse_with("Private", &configs).unwrap();
+        assert_eq!(network.base_url(), "http://10.0.0.5:8090");
+        assert_eq!(
+            network.config().solidity_url.as_deref(),
+            Some("http://10.0.0.5:8091")
+        );
+        assert_eq!(network.genesis_block_id(), None);
+        assert!(Network::parse_with("other", &configs).is_err());
+        assert_eq!(
+            Network::Nile.config().genesis_block_id.as_deref(),
+            Some(NILE_GENESIS)
+        );
+    }
+}
@@ -52,10 +52,14 @@ pub struct GetAccountParams {
 }
 
 impl GetAccountParams {
+    /// Sends the address in the form it was given. Use
+    /// [`Address::into_base58`] or [`Address::into_hex`] first if the
+    /// endpoint expects a particular form.
     pub fn new(address: Address) -> GetAccountParams {
-        let (address, visible) = match address {
-            Address::Base58(addr) => (addr, true),
-            Address::Hex(addr) => (addr, false),
+        let visible = address.is_visible();
+        let address = match address {
+            Address::Base58(addr) => addr,
+            Address::Hex(addr) => addr,
         };
 
         GetAccountParams { address, visible }
@@ -98,16 +102,19 @@ impl serde::Serialize for EmptyBody {
 pub struct GetContractParams {
     #[serde(rename = "value")]
     address: String,
+    // true if address is in base58...
+    visible: bool,
 }
 
 impl GetContractParams {
     pub fn new(address: Address) -> GetContractParams {
+        let visible = address.is_visible();
         let address = match address {
             Address::Base58(addr) => addr,
             Address::Hex(addr) => addr,
         };
 
-        GetContractParams { address }
+        GetContractParams { address, visible }
     }
 }
 
@@ -191,12 +198,12 @@ pub struct BroadcastHexParams {
     pub transaction: String
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 pub struct FreezeBalanceV2Params {
-    owner_address: String,
-    resource: String,
-    frozen_balance: i64,
-    visible: bool,
+    pub owner_address: String,
+    pub resource: String,
+    pub frozen_balance: i64,
+    pub visible: bool,
 }
 
 impl FreezeBalanceV2Params {
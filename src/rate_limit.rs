@@ -0,0 +1,283 @@
+//! Client-side rate limiting.
+//!
+//! TronGrid enforces per API key quotas and bans keys that keep exceeding
+//! them, with separate limits for the `/v1` event API and the `/wallet`
+//! APIs. A [`RateLimiter`] holds one token bucket per [`EndpointClass`];
+//! every request, including retries, takes a token first and waits while
+//! the bucket is empty. The limiter lives behind an `Arc`, so clones of a
+//! [`Client`](../client/struct.Client.html) and all their tasks share it.
+//!
+//! When a request is refused for the rate limit anyway, with a 429 or
+//! TronGrid's 403, the bucket halves its rate and empties; every successful response then wins back a little of the
+//! configured rate (additive increase, multiplicative decrease).
+
+use crate::error::{Error, Result};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Lowest fraction of the configured rate adaptive backoff goes down to.
+const MIN_RATE_FACTOR: f64 = 1.0 / 16.0;
+
+/// Fraction of the configured rate regained per successful response.
+const RATE_RECOVERY: f64 = 0.02;
+
+/// Group of endpoints sharing a quota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    /// `/wallet`, `/walletsolidity` and `/walletpbft`.
+    Wallet,
+    /// The `/v1` event and account API.
+    Event,
+}
+
+impl EndpointClass {
+    /// Classifies a request path.
+    pub fn of(path: &str) -> EndpointClass {
+        if path.trim_start_matches('/').starts_with("v1/") {
+            EndpointClass::Event
+        } else {
+            EndpointClass::Wallet
+        }
+    }
+}
+
+/// Sustained rate and burst size of a token bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    requests_per_second: f64,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// `requests_per_second` with a burst of one second worth of requests.
+    /// The rate must be positive and finite; this is checked when the
+    /// limit is put in a [`RateLimiter`].
+    pub fn per_second(requests_per_second: f64) -> Self {
+        RateLimit {
+            requests_per_second,
+            burst: requests_per_second.ceil().max(1.0) as u32,
+        }
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    /// Requests that may be sent at once after a quiet period.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !(self.requests_per_second.is_finite() && self.requests_per_second > 0.0) {
+            return Err(Error::InvalidConfig(format!(
+                "rate limit of {} requests per second",
+                self.requests_per_second
+            )));
+        }
+        if self.burst == 0 {
+            return Err(Error::InvalidConfig("rate limit burst of 0".into()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last: Instant,
+    rate_factor: f64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    state: Mutex<BucketState>,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Bucket {
+            limit,
+            state: Mutex::new(BucketState {
+                tokens: limit.burst as f64,
+                last: Instant::now(),
+                rate_factor: 1.0,
+            }),
+        }
+    }
+
+    /// Takes a token, or returns how long to wait for the next one.
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let rate = self.limit.requests_per_second * state.rate_factor;
+        let elapsed = now.duration_since(state.last).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * rate).min(self.limit.burst as f64);
+        state.last = now;
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - state.tokens) / rate))
+        }
+    }
+
+    fn on_rate_limited(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.rate_factor = (state.rate_factor / 2.0).max(MIN_RATE_FACTOR);
+        state.tokens = 0.0;
+    }
+
+    fn on_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.rate_factor = (state.rate_factor + RATE_RECOVERY).min(1.0);
+    }
+
+    fn current_rate(&self) -> f64 {
+        self.limit.requests_per_second * self.state.lock().unwrap().rate_factor
+    }
+}
+
+/// Token buckets per [`EndpointClass`]. Classes without a limit are not
+/// throttled.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    wallet: Option<Bucket>,
+    event: Option<Bucket>,
+}
+
+impl RateLimiter {
+    /// A limiter that does not throttle anything until limits are added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The same limit, but a separate bucket, for every class.
+    pub fn uniform(limit: RateLimit) -> Result<Self> {
+        Self::new()
+            .with_limit(EndpointClass::Wallet, limit)?
+            .with_limit(EndpointClass::Event, limit)
+    }
+
+    /// Sets the limit of `class`. Fails with [`Error::InvalidConfig`] if
+    /// the rate is not positive and finite.
+    pub fn with_limit(mut self, class: EndpointClass, limit: RateLimit) -> Result<Self> {
+        limit.validate()?;
+        *self.bucket_slot(class) = Some(Bucket::new(limit));
+        Ok(self)
+    }
+
+    fn bucket_slot(&mut self, class: EndpointClass) -> &mut Option<Bucket> {
+        match class {
+            EndpointClass::Wallet => &mut self.wallet,
+            EndpointClass::Event => &mut self.event,
+        }
+    }
+
+    fn bucket(&self, class: EndpointClass) -> Option<&Bucket> {
+        match class {
+            EndpointClass::Wallet => self.wallet.as_ref(),
+            EndpointClass::Event => self.event.as_ref(),
+        }
+    }
+
+    /// Waits until a request of `class` may be sent.
+    pub async fn acquire(&self, class: EndpointClass) {
+        if let Some(bucket) = self.bucket(class) {
+            while let Some(wait) = bucket.try_acquire() {
+                tokio::time::sleep(wait).await;
+            }
+        }
+    }
+
+    /// Slows `class` down after a response refused for the rate limit.
+    pub fn on_rate_limited(&self, class: EndpointClass) {
+        if let Some(bucket) = self.bucket(class) {
+            bucket.on_rate_limited();
+        }
+    }
+
+    /// Lets `class` recover towards its configured rate.
+    pub fn on_success(&self, class: EndpointClass) {
+        if let Some(bucket) = self.bucket(class) {
+            bucket.on_success();
+        }
+    }
+
+    /// Current requests per second of `class`, after adaptive backoff.
+    pub fn current_rate(&self, class: EndpointClass) -> Option<f64> {
+        self.bucket(class).map(Bucket::current_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_paths() {
+        assert_eq!(
+            EndpointClass::of("/v1/contracts/T/events"),
+            EndpointClass::Event
+        );
+        assert_eq!(
+            EndpointClass::of("/walletsolidity/getaccount"),
+            EndpointClass::Wallet
+        );
+    }
+
+    #[tokio::test]
+    async fn throttles_to_rate() {
+        let limiter = RateLimiter::new()
+            .with_limit(
+                EndpointClass::Wallet,
+                RateLimit::per_second(50.0).with_burst(1),
+            )
+            .unwrap();
+        let started = Instant::now();
+        for _ in 0..6 {
+            limiter.acquire(EndpointClass::Wallet).await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(90));
+
+        // No limit for the event API.
+        let started = Instant::now();
+        for _ in 0..100 {
+            limiter.acquire(EndpointClass::Event).await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn backs_off_on_rate_limit() {
+        let limiter = RateLimiter::uniform(RateLimit::per_second(10.0)).unwrap();
+        limiter.on_rate_limited(EndpointClass::Event);
+        limiter.on_rate_limited(EndpointClass::Event);
+        assert_eq!(limiter.current_rate(EndpointClass::Event), Some(2.5));
+        assert_eq!(limiter.current_rate(EndpointClass::Wallet), Some(10.0));
+
+        for _ in 0..100 {
+            limiter.on_success(EndpointClass::Event);
+        }
+        assert_eq!(limiter.current_rate(EndpointClass::Event), Some(10.0));
+    }
+
+    #[test]
+    fn rejects_invalid_limits() {
+        for rate in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(RateLimiter::uniform(RateLimit::per_second(*rate)).is_err());
+        }
+        assert_eq!(RateLimit::per_second(0.5).with_burst(0).burst(), 1);
+        let client = crate::client::Client::builder()
+            .rate_limit(RateLimit::per_second(0.0))
+            .build();
+        assert!(matches!(client, Err(Error::InvalidConfig(_))));
+    }
+}
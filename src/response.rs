@@ -37,5 +37,6 @@ pub use constant_contract::EstimateEnergyResponse;
 pub use account_resource::AccountResource;
 pub use transaction::CreateTransactionResponse;
 pub use transaction::BroadcastHexResponse;
+pub use transaction::BroadcastTransactionResponse;
 // pub use error::Error as ResponseError;
 
@@ -21,6 +21,12 @@ pub struct Account {
     pub asset_v2: Vec<AssetV2>,
     #[serde(rename = "free_asset_net_usageV2", default)]
     pub free_asset_net_usage_v2: Vec<FreeAssetNetUsageV2>,
+    #[serde(rename = "owner_permission", default)]
+    pub owner_permission: Option<Permission>,
+    #[serde(rename = "witness_permission", default)]
+    pub witness_permission: Option<Permission>,
+    #[serde(rename = "active_permission", default)]
+    pub active_permission: Vec<Permission>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
@@ -50,3 +56,30 @@ pub struct FreeAssetNetUsageV2 {
     pub key: String,
     pub value: i64,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Permission {
+    // "Owner" is omitted by the node
+    #[serde(rename = "type", default)]
+    pub type_field: Option<String>,
+    #[serde(default)]
+    pub id: i32,
+    #[serde(rename = "permission_name", default)]
+    pub permission_name: String,
+    pub threshold: i64,
+    #[serde(rename = "parent_id", default)]
+    pub parent_id: Option<i32>,
+    // hex encoded bitmask of allowed contract types, active permissions only
+    #[serde(default)]
+    pub operations: Option<String>,
+    #[serde(default)]
+    pub keys: Vec<PermissionKey>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionKey {
+    pub address: String,
+    pub weight: i64,
+}
@@ -16,17 +16,27 @@ pub struct Block {
 pub struct BlockHeader {
     #[serde(rename = "raw_data")]
     pub raw_data: RawData,
-    #[serde(rename = "witness_signature")]
+    // the genesis block is not signed
+    #[serde(rename = "witness_signature", default)]
     pub witness_signature: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RawData {
+    // left out of the genesis block, where both are zero
+    #[serde(default)]
     pub number: i64,
     pub tx_trie_root: String,
     #[serde(rename = "witness_address")]
     pub witness_address: String,
     pub parent_hash: String,
+    #[serde(default)]
     pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i32>,
+    #[serde(rename = "witness_id", default, skip_serializing_if = "Option::is_none")]
+    pub witness_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_state_root: Option<String>,
 }
@@ -1,6 +1,4 @@
-use serde::{Deserialize, de::{DeserializeOwned, Visitor, Error}, Deserializer};
-use log::debug;
-
+use serde::{Deserialize, de::{DeserializeOwned, Error}, Deserializer};
 
 #[derive(Debug)]
 pub struct EventResponse<T: DeserializeOwned> {
@@ -75,6 +73,6 @@ where
     D: Deserializer<'de>,
 {
     let hex: String = Deserialize::deserialize(deserializer)?;
-    Ok(u64::from_str_radix(&hex, 10).map_err(D::Error::custom)?)
+    hex.parse::<u64>().map_err(D::Error::custom)
 }
 
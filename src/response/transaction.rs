@@ -1,8 +1,11 @@
 #[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Transaction {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub visible: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ret: Option<Vec<Ret>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub signature: Option<Vec<String>>,
     #[serde(rename = "txID")]
     pub tx_id: String,
@@ -12,9 +15,23 @@ pub struct Transaction {
     pub raw_data_hex: String,
 }
 
+impl Transaction {
+    /// Protobuf encoding of the transaction (`raw_data` plus `signature`),
+    /// hex encoded, as accepted by
+    /// [`Client::broadcast_hex`](../../client/struct.Client.html#method.broadcast_hex).
+    pub fn to_broadcast_hex(&self) -> crate::error::Result<String> {
+        Ok(hex::encode(crate::proto::encode_transaction(self)?))
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Ret {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ret: Option<String>,
+    #[serde(default)]
     pub contract_ret: String,
 }
 
@@ -22,16 +39,23 @@ pub struct Ret {
 #[serde(rename_all = "camelCase")]
 pub struct RawData {
     pub contract: Vec<Contract>,
-    #[serde(rename = "ref_block_bytes")]
+    // the transactions of the genesis block have none of these three
+    #[serde(rename = "ref_block_bytes", default)]
     pub ref_block_bytes: String,
-    #[serde(rename = "ref_block_hash")]
+    #[serde(rename = "ref_block_hash", default)]
     pub ref_block_hash: String,
+    #[serde(default)]
     pub expiration: i64,
     // we need default here because there is one known transaction that does not have the
     // timestamp field.... WTF
     // tron get_transaction_by_id 8b8e052a058b228a7aacc24e57bf328096fb6c8878cbd42cb226bf4c626377d8
     #[serde(default)]
     pub timestamp: i64,
+    #[serde(rename = "fee_limit", skip_serializing_if = "Option::is_none")]
+    pub fee_limit: Option<i64>,
+    // hex encoded memo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
@@ -40,6 +64,8 @@ pub struct Contract {
     pub parameter: Parameter,
     #[serde(rename = "type")]
     pub type_field: String,
+    #[serde(rename = "Permission_id", skip_serializing_if = "Option::is_none")]
+    pub permission_id: Option<i32>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
@@ -53,31 +79,54 @@ pub struct Parameter {
 #[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Value {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<String>,
     #[serde(rename = "owner_address")]
     pub owner_address: String,
-    #[serde(rename = "contract_address")]
+    #[serde(rename = "contract_address", skip_serializing_if = "Option::is_none")]
     pub contract_address: Option<String>,
-    #[serde(rename = "call_value")]
+    #[serde(rename = "call_value", skip_serializing_if = "Option::is_none")]
     pub call_value: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub amount: Option<i64>,
-    #[serde(rename = "asset_name")]
+    #[serde(rename = "asset_name", skip_serializing_if = "Option::is_none")]
     pub asset_name: Option<String>,
-    #[serde(rename = "to_address")]
+    #[serde(rename = "to_address", skip_serializing_if = "Option::is_none")]
     pub to_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub resource: Option<String>,
-    #[serde(rename = "frozen_balance")]
+    #[serde(rename = "frozen_balance", skip_serializing_if = "Option::is_none")]
     pub frozen_balance: Option<i64>,
-    #[serde(rename = "unfreeze_balance")]
+    #[serde(rename = "unfreeze_balance", skip_serializing_if = "Option::is_none")]
     pub unfreeze_balance: Option<i64>,
-    #[serde(rename = "receiver_address")]
+    #[serde(rename = "receiver_address", skip_serializing_if = "Option::is_none")]
     pub receiver_address: Option<String>,
-    #[serde(rename = "lock_period")]
+    #[serde(rename = "lock_period", skip_serializing_if = "Option::is_none")]
     pub lock_period: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub lock: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub balance: Option<i64>,
-    #[serde(rename = "account_address")]
+    #[serde(rename = "account_address", skip_serializing_if = "Option::is_none")]
     pub account_address: Option<String>,
+    // TRC10 token sent along with a contract call
+    #[serde(rename = "call_token_value", skip_serializing_if = "Option::is_none")]
+    pub call_token_value: Option<i64>,
+    #[serde(rename = "token_id", skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub votes: Option<Vec<Vote>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support: Option<bool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vote {
+    #[serde(rename = "vote_address")]
+    pub vote_address: String,
+    #[serde(rename = "vote_count")]
+    pub vote_count: i64,
 }
 
 
@@ -99,4 +148,84 @@ pub struct BroadcastHexResponse {
     pub code: Option<String>,
     pub message: Option<String>,
     pub transaction: Option<String>,
-}
\ No newline at end of file
+}
+
+/// Result code of `/wallet/broadcasttransaction` and `/wallet/broadcasthex`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BroadcastCode {
+    Success,
+    Sigerror,
+    BandwithError,
+    DupTransactionError,
+    TaposError,
+    TooBigTransactionError,
+    TransactionExpirationError,
+    ServerBusy,
+    NoConnection,
+    NotEnoughEffectiveConnection,
+    BlockUnsolidified,
+    ContractValidateError,
+    ContractExeError,
+    OtherError,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+pub struct BroadcastTransactionResponse {
+    #[serde(default)]
+    pub result: bool,
+    pub txid: Option<String>,
+    pub code: Option<BroadcastCode>,
+    /// Hex encoded error message, see [`message_text`](#method.message_text).
+    pub message: Option<String>,
+}
+
+impl BroadcastTransactionResponse {
+    /// Decodes the hex encoded `message`. Messages that are not hex are
+    /// returned unchanged.
+    pub fn message_text(&self) -> Option<String> {
+        self.message.as_ref().map(|message| {
+            hex::decode(message)
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .unwrap_or_else(|_| message.clone())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_broadcast_error() {
+        let res: BroadcastTransactionResponse = serde_json::from_str(
+            r#"{"code":"SIGERROR","txid":"77ddfa7093cc5f745c0d3a54abb89ef070f983343c05e0f89e5a52f3e5401299","message":"76616c6964617465207369676e6174757265206572726f72"}"#,
+        )
+        .unwrap();
+        assert!(!res.result);
+        assert_eq!(res.code, Some(BroadcastCode::Sigerror));
+        assert_eq!(res.message_text().unwrap(), "validate signature error");
+
+        let res: BroadcastTransactionResponse =
+            serde_json::from_str(r#"{"result":true,"code":"SUCCESS","txid":"ab"}"#).unwrap();
+        assert!(res.result);
+        assert_eq!(res.code, Some(BroadcastCode::Success));
+
+        let res: BroadcastTransactionResponse =
+            serde_json::from_str(r#"{"code":"SOMETHING_NEW"}"#).unwrap();
+        assert_eq!(res.code, Some(BroadcastCode::Unknown));
+    }
+
+    #[test]
+    fn serializes_without_nulls() {
+        let block: crate::response::Block =
+            serde_json::from_str(include_str!("block.json")).unwrap();
+        for tx in &block.transactions {
+            let json = serde_json::to_string(tx).unwrap();
+            assert!(!json.contains("null"));
+            assert_eq!(&serde_json::from_str::<Transaction>(&json).unwrap(), tx);
+        }
+    }
+}
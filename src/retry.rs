@@ -0,0 +1,209 @@
+//! Retry policy for transient failures.
+//!
+//! A request is retried when the connection fails, when the node or a proxy
+//! in front of it answers 429, 502, 503 or 504, and, for lookups of fresh
+//! data, when the node answers `{}` because the transaction or block is not
+//! available on that node yet. Delays grow exponentially and are jittered so
+//! that many clients do not retry in lockstep.
+//!
+//! A retryable failure is first sent to the next healthy endpoint, if the
+//! client has several, and only backs off once every endpoint was tried.
+//!
+//! Whether an endpoint may be retried is decided by [`RetryClass::of`]:
+//! queries and transaction builders have no side effects and are retried,
+//! broadcasts are only retried when the connection could not be made, so a
+//! transaction that may have reached the node is never sent twice. They are
+//! never failed over, so a broadcast is sent once unless `max_retries`
+//! allows more.
+
+use crate::transport::TransportError;
+use rand_core::{OsRng, RngCore};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Endpoints whose request changes the state of the network.
+const NOT_IDEMPOTENT: &[&str] = &["broadcasttransaction", "broadcasthex"];
+
+/// Endpoints that answer `{}` for data the node does not have yet.
+const PENDING: &[&str] = &[
+    "gettransactionbyid",
+    "gettransactioninfobyid",
+    "gettransactioninfobyblocknum",
+    "getblockbynum",
+    "getblockbyid",
+];
+
+/// How requests to an endpoint may be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryClass {
+    /// Queries and transaction builders: retried on any transient failure.
+    Idempotent,
+    /// Lookups that answer `{}` until the data reaches the node: also
+    /// retried on empty responses.
+    Pending,
+    /// Broadcasts: only retried when the connection could not be made.
+    NotIdempotent,
+}
+
+impl RetryClass {
+    /// Classifies a request path such as `/wallet/broadcasttransaction` or
+    /// `/walletsolidity/gettransactionbyid`.
+    pub fn of(path: &str) -> RetryClass {
+        let path = path.split('?').next().unwrap_or_default();
+        let endpoint = path
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+        if NOT_IDEMPOTENT.contains(&endpoint) {
+            RetryClass::NotIdempotent
+        } else if PENDING.contains(&endpoint) {
+            RetryClass::Pending
+        } else {
+            RetryClass::Idempotent
+        }
+    }
+}
+
+/// Retry settings of a [`Client`](../client/struct.Client.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of a single delay.
+    pub max_backoff: Duration,
+    /// Factor applied to the delay after each retry.
+    pub multiplier: f64,
+    /// Randomizes each delay between half and all of its value.
+    pub jitter: bool,
+    /// Retries `{}` responses of [`RetryClass::Pending`] endpoints.
+    pub retry_empty: bool,
+    /// Sends a retryable request to the next healthy endpoint right away,
+    /// before counting a retry. Broadcasts are never failed over.
+    pub failover: bool,
+}
+
+impl Default for RetryPolicy {
+    /// 3 retries, starting at 250ms and doubling up to 5s, with jitter.
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: true,
+            retry_empty: true,
+            failover: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Sends every request exactly once, to one endpoint.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            failover: false,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a retryable request may go to another endpoint without
+    /// counting a retry.
+    pub fn fail_over(&self, class: RetryClass) -> bool {
+        self.failover && class != RetryClass::NotIdempotent
+    }
+
+    /// Delay before retry number `retry` (starting at 0).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let delay = self.initial_backoff.as_secs_f64() * self.multiplier.powi(retry as i32);
+        let delay = delay.min(self.max_backoff.as_secs_f64());
+        let delay = if self.jitter {
+            let unit = OsRng.next_u32() as f64 / u32::MAX as f64;
+            delay * (0.5 + unit / 2.0)
+        } else {
+            delay
+        };
+        Duration::from_secs_f64(delay)
+    }
+
+    /// Whether a failed request may be sent again.
+    pub fn retry_error(&self, class: RetryClass, err: &TransportError) -> bool {
+        match class {
+            RetryClass::NotIdempotent => err.is_connect(),
+            _ => err.is_connect() || err.is_timeout(),
+        }
+    }
+
+    /// Whether a response with `status` may be retried.
+    pub fn retry_status(&self, class: RetryClass, status: StatusCode) -> bool {
+        class != RetryClass::NotIdempotent && matches!(status.as_u16(), 429 | 502 | 503 | 504)
+    }
+
+    /// Whether a successful response with `body` means the data is not
+    /// available yet.
+    pub fn retry_body(&self, class: RetryClass, body: &str) -> bool {
+        self.retry_empty && class == RetryClass::Pending && body.trim() == "{}"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_endpoints() {
+        assert_eq!(
+            RetryClass::of("/wallet/broadcasttransaction"),
+            RetryClass::NotIdempotent
+        );
+        assert_eq!(
+            RetryClass::of("/walletsolidity/gettransactionbyid"),
+            RetryClass::Pending
+        );
+        assert_eq!(
+            RetryClass::of("/wallet/createtransaction"),
+            RetryClass::Idempotent
+        );
+        assert_eq!(
+            RetryClass::of("/v1/contracts/T/events?event_name=Transfer"),
+            RetryClass::Idempotent
+        );
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(250));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(10), Duration::from_secs(5));
+
+        let policy = RetryPolicy::default();
+        for _ in 0..20 {
+            let delay = policy.backoff(1);
+            assert!(delay >= Duration::from_millis(250) && delay <= Duration::from_millis(500));
+        }
+    }
+
+    #[test]
+    fn retries_only_safe_cases() {
+        let policy = RetryPolicy::default();
+        let unavailable = StatusCode::SERVICE_UNAVAILABLE;
+        assert!(policy.retry_status(RetryClass::Idempotent, unavailable));
+        assert!(!policy.retry_status(RetryClass::NotIdempotent, unavailable));
+        assert!(!policy.retry_status(RetryClass::Idempotent, StatusCode::BAD_REQUEST));
+
+        assert!(policy.fail_over(RetryClass::Idempotent));
+        assert!(!policy.fail_over(RetryClass::NotIdempotent));
+        assert!(!RetryPolicy::none().fail_over(RetryClass::Idempotent));
+
+        assert!(policy.retry_body(RetryClass::Pending, " {}\n"));
+        assert!(!policy.retry_body(RetryClass::Idempotent, "{}"));
+        assert!(!policy.retry_body(RetryClass::Pending, r#"{"txID":"ab"}"#));
+    }
+}
@@ -0,0 +1,254 @@
+//! Local transaction signing.
+//!
+//! Transactions returned by the `/wallet` builders (`create_transaction`,
+//! `freeze_balance_v2`, ...) are unsigned. A [`Signer`] signs their `txID`
+//! (the sha256 of `raw_data`) and appends the 65 byte `r || s || v`
+//! signature to `Transaction.signature`, after which the transaction can be
+//! sent with [`Client::broadcast_hex`].
+//!
+//! [`Client::broadcast_hex`]: ../client/struct.Client.html#method.broadcast_hex
+
+use crate::address::{Address, ADDRESS_PREFIX};
+use crate::error::{Error, Result};
+use crate::response::Transaction;
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use std::fmt;
+use std::str::FromStr;
+
+/// Length of a recoverable secp256k1 signature (`r || s || v`).
+pub const SIGNATURE_LEN: usize = 65;
+
+/// Something that can produce recoverable secp256k1 signatures for a Tron
+/// account.
+pub trait Signer {
+    /// Address of the account whose key is used for signing.
+    fn address(&self) -> Address;
+
+    /// Signs a 32 byte digest, returning `r || s || v` where `v` is the
+    /// recovery id plus 27, as produced by TronWeb.
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<[u8; SIGNATURE_LEN]>;
+
+    /// Signs `tx.tx_id` and appends the hex encoded signature to
+    /// `tx.signature`.
+    ///
+    /// Fails if `tx_id` is not the sha256 of `raw_data_hex`, so that a
+    /// signature is never produced for bytes other than the ones broadcast.
+    fn sign_transaction(&self, tx: &mut Transaction) -> Result<()> {
+        let hash = transaction_hash(tx)?;
+        let signature = self.sign_hash(&hash)?;
+        tx.signature
+            .get_or_insert_with(Vec::new)
+            .push(hex::encode(&signature[..]));
+        Ok(())
+    }
+}
+
+/// Returns the 32 byte `txID` of a transaction, checking that it matches
+/// `sha256(raw_data_hex)`.
+pub fn transaction_hash(tx: &Transaction) -> Result<[u8; 32]> {
+    let raw = hex::decode(&tx.raw_data_hex)
+        .map_err(|e| Error::InvalidTransaction(format!("raw_data_hex: {}", e)))?;
+    let tx_id =
+        hex::decode(&tx.tx_id).map_err(|e| Error::InvalidTransaction(format!("txID: {}", e)))?;
+
+    let hash: [u8; 32] = Sha256::digest(&raw).into();
+    if tx_id[..] != hash[..] {
+        return Err(Error::InvalidTransaction(format!(
+            "txID {} does not match sha256(raw_data) {}",
+            tx.tx_id,
+            hex::encode(hash)
+        )));
+    }
+    Ok(hash)
+}
+
+/// Derives the Tron address of a secp256k1 public key:
+/// `0x41 || keccak256(uncompressed_pubkey[1..])[12..]`.
+pub fn public_key_to_address(key: &VerifyingKey) -> Address {
+    let point = key.to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+
+    let mut raw = Vec::with_capacity(21);
+    raw.push(ADDRESS_PREFIX);
+    raw.extend_from_slice(&hash[12..]);
+    Address::Hex(hex::encode(raw))
+}
+
+/// Recovers the address that produced a 65 byte `r || s || v` signature
+/// over `hash`. `v` may be either 0/1 or 27/28, and high-S signatures are
+/// accepted.
+pub fn recover_address(hash: &[u8; 32], signature: &[u8]) -> Result<Address> {
+    if signature.len() != SIGNATURE_LEN {
+        return Err(Error::Signature(format!(
+            "expected {} bytes, got {}",
+            SIGNATURE_LEN,
+            signature.len()
+        )));
+    }
+    let v = signature[64];
+    let v = if v >= 27 { v - 27 } else { v };
+    let mut recovery_id = RecoveryId::from_byte(v)
+        .ok_or_else(|| Error::Signature(format!("invalid recovery id {}", signature[64])))?;
+    let mut sig =
+        Signature::from_slice(&signature[..64]).map_err(|e| Error::Signature(e.to_string()))?;
+    if let Some(normalized) = sig.normalize_s() {
+        sig = normalized;
+        recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+    }
+
+    let key = VerifyingKey::recover_from_prehash(hash, &sig, recovery_id)
+        .map_err(|e| Error::Signature(e.to_string()))?;
+    Ok(public_key_to_address(&key))
+}
+
+/// Recovers the address behind every entry of `tx.signature`, in order.
+pub fn recover_signers(tx: &Transaction) -> Result<Vec<Address>> {
+    let hash = transaction_hash(tx)?;
+    tx.signature
+        .iter()
+        .flatten()
+        .map(|signature| {
+            let bytes = hex::decode(signature).map_err(|e| Error::Signature(e.to_string()))?;
+            recover_address(&hash, &bytes)
+        })
+        .collect()
+}
+
+/// A [`Signer`] holding a raw secp256k1 private key in memory.
+#[derive(Clone)]
+pub struct PrivateKeySigner {
+    key: SigningKey,
+    address: Address,
+}
+
+impl PrivateKeySigner {
+    /// Creates a signer from a 32 byte private key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PrivateKeySigner> {
+        let key = SigningKey::from_slice(bytes).map_err(|e| Error::InvalidKey(e.to_string()))?;
+        Ok(PrivateKeySigner::from_signing_key(key))
+    }
+
+    /// Creates a signer from a 64 character hex private key (with or without
+    /// `0x`).
+    pub fn from_hex(s: &str) -> Result<PrivateKeySigner> {
+        let s = s.trim();
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let bytes = hex::decode(s).map_err(|e| Error::InvalidKey(e.to_string()))?;
+        PrivateKeySigner::from_bytes(&bytes)
+    }
+
+    pub fn from_signing_key(key: SigningKey) -> PrivateKeySigner {
+        let address = public_key_to_address(key.verifying_key());
+        PrivateKeySigner { key, address }
+    }
+
+    pub fn signing_key(&self) -> &SigningKey {
+        &self.key
+    }
+
+    pub fn verifying_key(&self) -> &VerifyingKey {
+        self.key.verifying_key()
+    }
+}
+
+impl Signer for PrivateKeySigner {
+    fn address(&self) -> Address {
+        self.address.clone()
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<[u8; SIGNATURE_LEN]> {
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(hash)
+            .map_err(|e| Error::Signature(e.to_string()))?;
+
+        let mut out = [0u8; SIGNATURE_LEN];
+        out[..64].copy_from_slice(&signature.to_bytes());
+        out[64] = recovery_id.to_byte() + 27;
+        Ok(out)
+    }
+}
+
+impl FromStr for PrivateKeySigner {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        PrivateKeySigner::from_hex(s)
+    }
+}
+
+// Never print the private key.
+impl fmt::Debug for PrivateKeySigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateKeySigner")
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+
+    #[test]
+    fn derives_address() {
+        let signer = PrivateKeySigner::from_hex(KEY).unwrap();
+        assert_eq!(
+            signer.address().to_evm_hex().unwrap(),
+            "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf"
+        );
+    }
+
+    #[test]
+    fn signature_recovers_to_signer() {
+        let signer = PrivateKeySigner::from_hex(KEY).unwrap();
+        let hash: [u8; 32] = Sha256::digest(b"tron").into();
+        let sig = signer.sign_hash(&hash).unwrap();
+
+        assert!(sig[64] == 27 || sig[64] == 28);
+        let signature = Signature::from_slice(&sig[..64]).unwrap();
+        let recovery_id = RecoveryId::from_byte(sig[64] - 27).unwrap();
+        let key = VerifyingKey::recover_from_prehash(&hash, &signature, recovery_id).unwrap();
+        assert_eq!(public_key_to_address(&key), signer.address());
+    }
+
+    #[test]
+    fn recovers_signers_of_block_transactions() {
+        let block: crate::response::Block =
+            serde_json::from_str(include_str!("response/block.json")).unwrap();
+        for tx in &block.transactions {
+            let owner = &tx.raw_data.contract[0].parameter.value.owner_address;
+            assert_eq!(
+                recover_signers(tx).unwrap(),
+                vec![Address::Hex(owner.clone())]
+            );
+        }
+    }
+
+    #[test]
+    fn sign_transaction_checks_tx_id() {
+        let signer = PrivateKeySigner::from_hex(KEY).unwrap();
+        let raw = hex::decode("0a0201").unwrap();
+        let mut tx = Transaction {
+            raw_data_hex: hex::encode(&raw),
+            tx_id: hex::encode(Sha256::digest(&raw)),
+            ..Default::default()
+        };
+
+        signer.sign_transaction(&mut tx).unwrap();
+        assert_eq!(recover_signers(&tx).unwrap(), vec![signer.address()]);
+        let signature = tx.signature.as_ref().unwrap()[0].clone();
+        assert_eq!(signature.len(), SIGNATURE_LEN * 2);
+        assert_eq!(
+            tx.to_broadcast_hex().unwrap(),
+            format!("0a030a02011241{}", signature)
+        );
+
+        tx.tx_id = hex::encode([0u8; 32]);
+        assert!(signer.sign_transaction(&mut tx).is_err());
+    }
+}
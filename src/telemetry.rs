@@ -0,0 +1,119 @@
+//! Tracing and metrics of client requests.
+//!
+//! Every request runs in a `tron_request` [`tracing`] span carrying the
+//! method, the endpoint, and once it is done the final status, the latency
+//! in milliseconds and the number of retries. Attempts, failovers and
+//! backoffs are logged as events inside that span.
+//!
+//! Metrics go through the [`metrics`] facade and are dropped unless the
+//! application installs a recorder:
+//!
+//! - `tron_client_requests_total{endpoint, status}`: finished requests,
+//!   `status` being the final HTTP status or `error`.
+//! - `tron_client_errors_total{endpoint, kind}`: failed attempts, `kind`
+//!   being `transport` or `status` (a non-2xx response).
+//! - `tron_client_retries_total{endpoint}`: attempts after the first.
+//! - `tron_client_request_duration_seconds{endpoint}`: histogram of request
+//!   latency, retries included.
+//!
+//! Endpoints are labeled by path without query; addresses in `/v1` paths
+//! are replaced by `{address}` to keep the number of series bounded. With
+//! the `prometheus` feature, [`install_prometheus`] installs a recorder
+//! that renders the metrics in the Prometheus text format.
+
+use std::time::Duration;
+
+pub const REQUESTS_TOTAL: &str = "tron_client_requests_total";
+pub const ERRORS_TOTAL: &str = "tron_client_errors_total";
+pub const RETRIES_TOTAL: &str = "tron_client_retries_total";
+pub const REQUEST_DURATION: &str = "tron_client_request_duration_seconds";
+
+/// Histogram buckets of [`REQUEST_DURATION`], in seconds.
+pub const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Metric label of a request path, e.g. `/v1/contracts/{address}/events`.
+pub fn endpoint_label(path: &str) -> String {
+    let path = path.split('?').next().unwrap_or_default();
+    path.split('/')
+        .map(|segment| {
+            let base58 = segment.len() == 34 && segment.starts_with('T');
+            let hex = segment.len() >= 40 && segment.chars().all(|c| c.is_ascii_hexdigit());
+            if base58 || hex {
+                "{address}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Kind of a failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ErrorKind {
+    Transport,
+    Status,
+}
+
+impl ErrorKind {
+    fn label(self) -> &'static str {
+        match self {
+            ErrorKind::Transport => "transport",
+            ErrorKind::Status => "status",
+        }
+    }
+}
+
+pub(crate) fn record_error(endpoint: &str, kind: ErrorKind) {
+    metrics::counter!(ERRORS_TOTAL, "endpoint" => endpoint.to_string(), "kind" => kind.label())
+        .increment(1);
+}
+
+/// Records a finished request; `status` is `None` if no response arrived.
+pub(crate) fn record_request(endpoint: &str, status: Option<u16>, latency: Duration, retries: u32) {
+    let status = status
+        .map(|status| status.to_string())
+        .unwrap_or_else(|| "error".into());
+    metrics::counter!(REQUESTS_TOTAL, "endpoint" => endpoint.to_string(), "status" => status)
+        .increment(1);
+    if retries > 0 {
+        metrics::counter!(RETRIES_TOTAL, "endpoint" => endpoint.to_string())
+            .increment(retries as u64);
+    }
+    metrics::histogram!(REQUEST_DURATION, "endpoint" => endpoint.to_string()).record(latency);
+}
+
+/// Installs a global Prometheus recorder with [`REQUEST_DURATION`] as a
+/// histogram. Call `render()` on the handle to get the text exposition,
+/// e.g. from a `/metrics` handler.
+#[cfg(feature = "prometheus")]
+pub fn install_prometheus() -> crate::error::Result<metrics_exporter_prometheus::PrometheusHandle> {
+    use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.into()), DURATION_BUCKETS)
+        .and_then(|builder| builder.install_recorder())
+        .map_err(|e| crate::error::Error::InvalidConfig(format!("prometheus: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_endpoints() {
+        assert_eq!(endpoint_label("/wallet/getnowblock"), "/wallet/getnowblock");
+        assert_eq!(
+            endpoint_label(
+                "/v1/contracts/TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t/events?event_name=Transfer"
+            ),
+            "/v1/contracts/{address}/events"
+        );
+        assert_eq!(
+            endpoint_label("/v1/accounts/41a614f803b6fd780986a42c78ec9c7f77e6ded13c"),
+            "/v1/accounts/{address}"
+        );
+    }
+}
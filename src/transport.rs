@@ -0,0 +1,341 @@
+//! The HTTP layer under [`Client`](../client/struct.Client.html).
+//!
+//! A [`Transport`] takes a method, a URL and an optional JSON body and
+//! returns a status and a body; everything above it (endpoints, API keys,
+//! retries, failover, decoding) is done by the client. [`ReqwestTransport`]
+//! is the default. [`MockTransport`] answers from programmed responses and
+//! records requests, so endpoint methods can be tested without a network:
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use tron_api_client::Client;
+//! # use tron_api_client::transport::MockTransport;
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let mock = Arc::new(MockTransport::new());
+//! mock.respond(
+//!     "POST",
+//!     "/wallet/getnowblock",
+//!     200,
+//!     r#"{"blockID":"00","block_header":{"raw_data":{"number":7,"txTrieRoot":"","witness_address":"","parentHash":"","timestamp":0},"witness_signature":""}}"#,
+//! );
+//! let client = Client::builder().transport(mock.clone()).build().unwrap();
+//! let block = client.get_now_block().await.unwrap();
+//! assert_eq!(block.block_header.raw_data.number, 7);
+//! assert_eq!(mock.requests()[0].path(), "/wallet/getnowblock");
+//! # }
+//! ```
+
+use reqwest::header::HeaderMap;
+use reqwest::{Client as HttpClient, Method};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+/// A request as handed to a [`Transport`].
+#[derive(Debug, Clone)]
+pub struct TransportRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    /// JSON body of `POST` requests.
+    pub body: Option<Value>,
+    pub timeout: Option<Duration>,
+}
+
+impl TransportRequest {
+    /// Path and query of the URL, e.g. `/wallet/getnowblock`.
+    pub fn path(&self) -> String {
+        match self.url.query() {
+            Some(query) => format!("{}?{}", self.url.path(), query),
+            None => self.url.path().to_string(),
+        }
+    }
+}
+
+/// Status and body of a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl TransportResponse {
+    pub fn new<S: Into<String>>(status: u16, body: S) -> Self {
+        TransportResponse {
+            status,
+            body: body.into(),
+        }
+    }
+}
+
+/// Failure to get a response at all.
+#[derive(Debug)]
+pub enum TransportError {
+    /// Error of the reqwest transport.
+    Http(reqwest::Error),
+    /// The connection could not be established; the request was not sent.
+    Connect(String),
+    /// No response arrived in time.
+    Timeout(String),
+    Other(String),
+}
+
+impl TransportError {
+    pub fn is_connect(&self) -> bool {
+        match self {
+            TransportError::Http(e) => e.is_connect(),
+            TransportError::Connect(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            TransportError::Http(e) => e.is_timeout(),
+            TransportError::Timeout(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Http(e) => write!(f, "{}", e),
+            TransportError::Connect(e) => write!(f, "connection failed: {}", e),
+            TransportError::Timeout(e) => write!(f, "timed out: {}", e),
+            TransportError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl StdError for TransportError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            TransportError::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(e: reqwest::Error) -> Self {
+        TransportError::Http(e)
+    }
+}
+
+/// Future returned by [`Transport::send`].
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<TransportResponse, TransportError>> + Send + 'a>>;
+
+/// Sends requests for a [`Client`](../client/struct.Client.html).
+pub trait Transport: fmt::Debug + Send + Sync {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_>;
+}
+
+/// The default transport, backed by a reqwest client.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: HttpClient,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: HttpClient) -> Self {
+        ReqwestTransport { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut req = self
+                .client
+                .request(request.method, request.url)
+                .headers(request.headers);
+            if let Some(body) = &request.body {
+                req = req.json(body);
+            }
+            if let Some(timeout) = request.timeout {
+                req = req.timeout(timeout);
+            }
+            let res = req.send().await?;
+            let status = res.status().as_u16();
+            Ok(TransportResponse::new(status, res.text().await?))
+        })
+    }
+}
+
+type Handler = Box<dyn Fn(&TransportRequest) -> Result<TransportResponse, TransportError> + Send>;
+
+struct Route {
+    method: Method,
+    path: String,
+    handlers: VecDeque<Handler>,
+}
+
+impl Route {
+    fn matches(&self, request: &TransportRequest) -> bool {
+        self.method == request.method
+            && (self.path == request.url.path() || self.path == request.path())
+    }
+}
+
+/// A transport answering from programmed responses.
+///
+/// Responses are registered per method and path (with or without query).
+/// Several responses for the same route are returned in order, the last
+/// one repeating. Requests without a route get a 404.
+#[derive(Default)]
+pub struct MockTransport {
+    routes: Mutex<Vec<Route>>,
+    requests: Mutex<Vec<TransportRequest>>,
+}
+
+impl fmt::Debug for MockTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockTransport")
+            .field("requests", &self.requests.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&self, method: &str, path: &str, handler: Handler) {
+        let method = Method::from_bytes(method.as_bytes()).expect("invalid method");
+        let mut routes = self.routes.lock().unwrap();
+        match routes
+            .iter_mut()
+            .find(|route| route.method == method && route.path == path)
+        {
+            Some(route) => route.handlers.push_back(handler),
+            None => routes.push(Route {
+                method,
+                path: path.to_string(),
+                handlers: vec![handler].into(),
+            }),
+        }
+    }
+
+    /// Answers `method path` with `status` and `body`.
+    pub fn respond<S: Into<String>>(&self, method: &str, path: &str, status: u16, body: S) {
+        let response = TransportResponse::new(status, body);
+        self.push(method, path, Box::new(move |_| Ok(response.clone())));
+    }
+
+    /// Answers `method path` with `200` and `body` serialized as JSON.
+    pub fn respond_json<T: serde::Serialize>(&self, method: &str, path: &str, body: &T) {
+        let body = serde_json::to_string(body).expect("could not serialize mock body");
+        self.respond(method, path, 200, body);
+    }
+
+    /// Fails `method path` with a connection error.
+    pub fn fail(&self, method: &str, path: &str) {
+        let message = format!("{} {}", method, path);
+        self.push(
+            method,
+            path,
+            Box::new(move |_| Err(TransportError::Connect(message.clone()))),
+        );
+    }
+
+    /// Answers `method path` by calling `handler` with the request.
+    pub fn respond_with<F>(&self, method: &str, path: &str, handler: F)
+    where
+        F: Fn(&TransportRequest) -> Result<TransportResponse, TransportError> + Send + 'static,
+    {
+        self.push(method, path, Box::new(handler));
+    }
+
+    /// Requests received so far, in order.
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn answer(&self, request: &TransportRequest) -> Result<TransportResponse, TransportError> {
+        let mut routes = self.routes.lock().unwrap();
+        match routes.iter_mut().find(|route| route.matches(request)) {
+            Some(route) => {
+                let handler = if route.handlers.len() > 1 {
+                    route.handlers.pop_front()
+                } else {
+                    None
+                };
+                match &handler {
+                    Some(handler) => handler(request),
+                    None => (route.handlers[0])(request),
+                }
+            }
+            None => Ok(TransportResponse::new(
+                404,
+                format!("no mock response for {} {}", request.method, request.path()),
+            )),
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        let response = self.answer(&request);
+        self.requests.lock().unwrap().push(request);
+        Box::pin(async move { response })
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        (**self).send(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: Method, url: &str) -> TransportRequest {
+        TransportRequest {
+            method,
+            url: Url::parse(url).unwrap(),
+            headers: HeaderMap::new(),
+            body: None,
+            timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_in_order_and_records() {
+        let mock = MockTransport::new();
+        mock.respond("POST", "/wallet/getnowblock", 503, "");
+        mock.respond("POST", "/wallet/getnowblock", 200, "{}");
+        mock.fail("GET", "/wallet/getnodeinfo");
+
+        let now = || request(Method::POST, "http://node/wallet/getnowblock");
+        assert_eq!(mock.send(now()).await.unwrap().status, 503);
+        assert_eq!(mock.send(now()).await.unwrap().status, 200);
+        assert_eq!(mock.send(now()).await.unwrap().status, 200);
+
+        let err = mock
+            .send(request(Method::GET, "http://node/wallet/getnodeinfo"))
+            .await
+            .unwrap_err();
+        assert!(err.is_connect());
+
+        let missing = mock
+            .send(request(Method::GET, "http://node/v1/x?limit=1"))
+            .await
+            .unwrap();
+        assert_eq!(missing.status, 404);
+        assert_eq!(mock.requests().len(), 5);
+        assert_eq!(mock.requests()[4].path(), "/v1/x?limit=1");
+    }
+}
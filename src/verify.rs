@@ -0,0 +1,697 @@
+//! Checks that a node-built transaction is the one that was asked for.
+//!
+//! The `/wallet` builders return both a JSON `raw_data` and the encoded
+//! `raw_data_hex`, and only the latter is hashed and signed. A node could
+//! return a harmless looking JSON while the hex pays someone else.
+//! [`verify_transaction`] decodes `raw_data_hex`, recomputes `txID`, and
+//! compares the decoded contract with both the JSON and the request params.
+//!
+//! Blocks are checked the same way: [`verify_block`] recomputes the block ID
+//! from the encoded header, recovers the witness from `witness_signature`,
+//! recomputes `txTrieRoot` and checks the `txID` and JSON of every
+//! transaction, and [`verify_block_list`] also follows the `parentHash`
+//! links.
+
+use crate::address::Address;
+use crate::error::{Error, Result};
+use crate::params::{
+    ActivateAccountParams, CreateTransactionParams, DelegateResourceParams, FreezeBalanceV2Params,
+    UnDelegateResourceParams, UnfreezeBalanceV2Params,
+};
+use crate::proto;
+use crate::response::transaction::{RawData, Transaction, Value};
+use crate::response::{Block, BlockList};
+use crate::signer::recover_address;
+use serde_json::{Map, Value as Json};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+const ADDRESS_FIELDS: &[&str] = &[
+    "owner_address",
+    "to_address",
+    "contract_address",
+    "receiver_address",
+    "account_address",
+];
+
+/// Describes how a transaction differs from what was expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// `txID` is not the sha256 of `raw_data_hex`.
+    TxId { returned: String, computed: String },
+
+    /// The JSON `raw_data` disagrees with the decoded `raw_data_hex`.
+    RawData {
+        field: String,
+        json: String,
+        encoded: String,
+    },
+
+    /// The transaction does not do what the params asked for.
+    Intent {
+        field: String,
+        requested: String,
+        encoded: String,
+    },
+
+    /// `blockID` does not match the encoded block header.
+    BlockId { returned: String, computed: String },
+
+    /// `witness_signature` was not made by `witness_address`.
+    Witness { expected: String, recovered: String },
+
+    /// `txTrieRoot` is not the Merkle root of the block's transactions.
+    TxTrieRoot { returned: String, computed: String },
+
+    /// A block does not follow the previous block of a list.
+    ParentHash {
+        number: i64,
+        parent_hash: String,
+        previous: String,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::TxId { returned, computed } => {
+                write!(f, "txID {} but raw_data hashes to {}", returned, computed)
+            }
+            Mismatch::RawData {
+                field,
+                json,
+                encoded,
+            } => write!(
+                f,
+                "raw_data.{} is {} in JSON but {} in raw_data_hex",
+                field, json, encoded
+            ),
+            Mismatch::Intent {
+                field,
+                requested,
+                encoded,
+            } => write!(
+                f,
+                "{} was requested as {} but is {} in raw_data_hex",
+                field, requested, encoded
+            ),
+            Mismatch::BlockId { returned, computed } => write!(
+                f,
+                "blockID {} but block header hashes to {}",
+                returned, computed
+            ),
+            Mismatch::Witness {
+                expected,
+                recovered,
+            } => write!(
+                f,
+                "block was signed by {} instead of witness {}",
+                recovered, expected
+            ),
+            Mismatch::TxTrieRoot { returned, computed } => write!(
+                f,
+                "txTrieRoot {} but transactions hash to {}",
+                returned, computed
+            ),
+            Mismatch::ParentHash {
+                number,
+                parent_hash,
+                previous,
+            } => write!(
+                f,
+                "block {} has parentHash {} but follows block {}",
+                number, parent_hash, previous
+            ),
+        }
+    }
+}
+
+/// Request params that describe the single contract a builder endpoint is
+/// expected to produce.
+pub trait TransactionIntent {
+    /// `Transaction.Contract.type`, e.g. `TransferContract`.
+    fn contract_type(&self) -> &'static str;
+
+    /// The contract payload the node should have built.
+    fn expected_value(&self) -> Value;
+}
+
+/// Verifies `tx` before it is signed:
+///
+/// - `txID` must be `sha256(raw_data_hex)`,
+/// - the decoded `raw_data_hex` must equal the JSON `raw_data`,
+/// - it must contain exactly one contract, matching `intent`.
+///
+/// Returns [`Error::TransactionMismatch`] describing the first difference.
+pub fn verify_transaction<I: TransactionIntent + ?Sized>(
+    tx: &Transaction,
+    intent: &I,
+) -> Result<()> {
+    let decoded = verify_raw_data(tx)?;
+
+    let intent_mismatch = |field: &str, requested: String, encoded: String| {
+        Error::TransactionMismatch(Mismatch::Intent {
+            field: field.to_string(),
+            requested,
+            encoded,
+        })
+    };
+
+    if decoded.contract.len() != 1 {
+        return Err(intent_mismatch(
+            "contract.len()",
+            "1".into(),
+            decoded.contract.len().to_string(),
+        ));
+    }
+    let contract = &decoded.contract[0];
+    if contract.type_field != intent.contract_type() {
+        return Err(intent_mismatch(
+            "type",
+            intent.contract_type().into(),
+            contract.type_field.clone(),
+        ));
+    }
+
+    let requested = normalize_value(&serde_json::to_value(intent.expected_value())?)?;
+    let encoded = normalize_value(&serde_json::to_value(&contract.parameter.value)?)?;
+    if let Some((field, requested, encoded)) = diff("", &requested, &encoded) {
+        return Err(intent_mismatch(&field, requested, encoded));
+    }
+
+    Ok(())
+}
+
+/// Verifies that `txID` is `sha256(raw_data_hex)` and that the JSON
+/// `raw_data` equals the decoded `raw_data_hex`, and returns the decoded
+/// `raw_data`. This is [`verify_transaction`] without an intent, e.g. for
+/// the transactions of a block.
+pub fn verify_raw_data(tx: &Transaction) -> Result<RawData> {
+    let bytes = hex::decode(&tx.raw_data_hex)
+        .map_err(|e| Error::InvalidTransaction(format!("raw_data_hex: {}", e)))?;
+
+    let computed = proto::transaction_id(&bytes);
+    if !computed.eq_ignore_ascii_case(&tx.tx_id) {
+        return Err(Error::TransactionMismatch(Mismatch::TxId {
+            returned: tx.tx_id.clone(),
+            computed,
+        }));
+    }
+
+    let decoded = proto::decode_raw_data(&bytes, tx.visible.unwrap_or_default())?;
+    let json = normalize_raw_data(&serde_json::to_value(&tx.raw_data)?)?;
+    let encoded = normalize_raw_data(&serde_json::to_value(&decoded)?)?;
+    if let Some((field, json, encoded)) = diff("", &json, &encoded) {
+        return Err(Error::TransactionMismatch(Mismatch::RawData {
+            field,
+            json,
+            encoded,
+        }));
+    }
+
+    Ok(decoded)
+}
+
+/// Merkle root of `transactions`, as stored in `txTrieRoot`.
+///
+/// Leaves are the sha256 of each encoded transaction (including `ret`);
+/// pairs are hashed together and an odd node is carried up unchanged. A
+/// block without transactions has the zero hash as root.
+pub fn tx_trie_root(transactions: &[Transaction]) -> Result<String> {
+    let mut level = transactions
+        .iter()
+        .map(|tx| Ok(Sha256::digest(proto::encode_block_transaction(tx)?).into()))
+        .collect::<Result<Vec<[u8; 32]>>>()?;
+    if level.is_empty() {
+        return Ok(hex::encode([0u8; 32]));
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Sha256::new();
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                }
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    Ok(hex::encode(level[0]))
+}
+
+/// Verifies a block returned by a node:
+///
+/// - `blockID` must match the encoded header and its number,
+/// - `witness_signature` must recover to `witness_address`,
+/// - `txTrieRoot` must be the Merkle root of `transactions`,
+/// - every transaction must pass [`verify_raw_data`].
+///
+/// Only the ID is checked for the genesis block: it is not signed and its
+/// transfers come from a placeholder owner that is not an address.
+///
+/// Returns [`Error::BlockMismatch`], or [`Error::TransactionMismatch`] for a
+/// transaction, describing the first difference.
+pub fn verify_block(block: &Block) -> Result<()> {
+    let header = &block.block_header;
+    let raw = proto::encode_block_header_raw(&header.raw_data)?;
+
+    let computed = proto::block_id(header.raw_data.number, &raw);
+    if !computed.eq_ignore_ascii_case(&block.block_id) {
+        return Err(Error::BlockMismatch(Mismatch::BlockId {
+            returned: block.block_id.clone(),
+            computed,
+        }));
+    }
+
+    if header.raw_data.number == 0 {
+        return Ok(());
+    }
+
+    let signature = hex::decode(&header.witness_signature)
+        .map_err(|e| Error::Signature(format!("witness_signature: {}", e)))?;
+    let recovered = recover_address(&Sha256::digest(&raw).into(), &signature)?;
+    let expected: Address = header.raw_data.witness_address.parse()?;
+    if recovered.to_bytes()? != expected.to_bytes()? {
+        return Err(Error::BlockMismatch(Mismatch::Witness {
+            expected: header.raw_data.witness_address.clone(),
+            recovered: recovered.to_string(),
+        }));
+    }
+
+    let computed = tx_trie_root(&block.transactions)?;
+    if !computed.eq_ignore_ascii_case(&header.raw_data.tx_trie_root) {
+        return Err(Error::BlockMismatch(Mismatch::TxTrieRoot {
+            returned: header.raw_data.tx_trie_root.clone(),
+            computed,
+        }));
+    }
+
+    for tx in &block.transactions {
+        verify_raw_data(tx)?;
+    }
+
+    Ok(())
+}
+
+/// Verifies every block of `list` with [`verify_block`] and checks that each
+/// block's `parentHash` is the ID of the block before it.
+pub fn verify_block_list(list: &BlockList) -> Result<()> {
+    let mut previous: Option<&Block> = None;
+    for block in &list.blocks {
+        verify_block(block)?;
+        if let Some(previous) = previous {
+            let raw = &block.block_header.raw_data;
+            if !raw.parent_hash.eq_ignore_ascii_case(&previous.block_id)
+                || raw.number != previous.block_header.raw_data.number + 1
+            {
+                return Err(Error::BlockMismatch(Mismatch::ParentHash {
+                    number: raw.number,
+                    parent_hash: raw.parent_hash.clone(),
+                    previous: previous.block_id.clone(),
+                }));
+            }
+        }
+        previous = Some(block);
+    }
+    Ok(())
+}
+
+/// Drops fields that protobuf does not distinguish from their default value
+/// and renders addresses as hex, so that values built from JSON, params and
+/// `raw_data_hex` compare equal.
+fn normalize_value(value: &Json) -> Result<Json> {
+    let mut out = Map::new();
+    for (key, v) in value.as_object().into_iter().flatten() {
+        let v = match v {
+            Json::Null => continue,
+            Json::Bool(false) => continue,
+            Json::Number(n) if n.as_i64() == Some(0) => continue,
+            Json::String(s) if s.is_empty() => continue,
+            Json::String(s) if key == "resource" && s == "BANDWIDTH" => continue,
+            Json::String(s) if ADDRESS_FIELDS.contains(&key.as_str()) => {
+                Json::String(s.parse::<Address>()?.to_hex()?)
+            }
+            Json::String(s) if key == "data" => Json::String(s.to_lowercase()),
+            v => v.clone(),
+        };
+        out.insert(key.clone(), v);
+    }
+    Ok(Json::Object(out))
+}
+
+fn normalize_raw_data(raw: &Json) -> Result<Json> {
+    let mut raw = raw.clone();
+    if let Some(obj) = raw.as_object_mut() {
+        obj.retain(|_, v| !v.is_null());
+        for contract in obj
+            .get_mut("contract")
+            .and_then(Json::as_array_mut)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(contract) = contract.as_object_mut() {
+                contract.retain(|_, v| !v.is_null());
+                if let Some(value) = contract
+                    .get_mut("parameter")
+                    .and_then(|p| p.get_mut("value"))
+                {
+                    *value = normalize_value(value)?;
+                }
+            }
+        }
+    }
+    Ok(raw)
+}
+
+/// Returns the path and both sides of the first difference between `a` and
+/// `b`.
+fn diff(path: &str, a: &Json, b: &Json) -> Option<(String, String, String)> {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match (a, b) {
+        (Json::Object(a_map), Json::Object(b_map)) => {
+            let mut keys: Vec<&String> = a_map.keys().chain(b_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter().find_map(|key| {
+                diff(
+                    &join(key),
+                    a_map.get(key).unwrap_or(&Json::Null),
+                    b_map.get(key).unwrap_or(&Json::Null),
+                )
+            })
+        }
+        (Json::Array(a_items), Json::Array(b_items)) if a_items.len() == b_items.len() => a_items
+            .iter()
+            .zip(b_items)
+            .enumerate()
+            .find_map(|(i, (a, b))| diff(&format!("{}[{}]", path, i), a, b)),
+        _ if a == b => None,
+        _ => Some((path.to_string(), a.to_string(), b.to_string())),
+    }
+}
+
+impl TransactionIntent for CreateTransactionParams {
+    fn contract_type(&self) -> &'static str {
+        "TransferContract"
+    }
+
+    fn expected_value(&self) -> Value {
+        Value {
+            owner_address: self.owner_address.clone(),
+            to_address: Some(self.to_address.clone()),
+            amount: Some(self.amount),
+            ..Default::default()
+        }
+    }
+}
+
+impl TransactionIntent for FreezeBalanceV2Params {
+    fn contract_type(&self) -> &'static str {
+        "FreezeBalanceV2Contract"
+    }
+
+    fn expected_value(&self) -> Value {
+        Value {
+            owner_address: self.owner_address.clone(),
+            resource: Some(self.resource.clone()),
+            frozen_balance: Some(self.frozen_balance),
+            ..Default::default()
+        }
+    }
+}
+
+impl TransactionIntent for UnfreezeBalanceV2Params {
+    fn contract_type(&self) -> &'static str {
+        "UnfreezeBalanceV2Contract"
+    }
+
+    fn expected_value(&self) -> Value {
+        Value {
+            owner_address: self.owner_address.clone(),
+            resource: Some(self.resource.clone()),
+            unfreeze_balance: Some(self.unfreeze_balance),
+            ..Default::default()
+        }
+    }
+}
+
+impl TransactionIntent for DelegateResourceParams {
+    fn contract_type(&self) -> &'static str {
+        "DelegateResourceContract"
+    }
+
+    fn expected_value(&self) -> Value {
+        Value {
+            owner_address: self.owner_address.clone(),
+            receiver_address: Some(self.receiver_address.clone()),
+            resource: Some(self.resource.clone()),
+            balance: Some(self.balance),
+            lock: Some(self.lock),
+            lock_period: Some(self.lock_period),
+            ..Default::default()
+        }
+    }
+}
+
+impl TransactionIntent for UnDelegateResourceParams {
+    fn contract_type(&self) -> &'static str {
+        "UnDelegateResourceContract"
+    }
+
+    fn expected_value(&self) -> Value {
+        Value {
+            owner_address: self.owner_address.clone(),
+            receiver_address: Some(self.receiver_address.clone()),
+            resource: Some(self.resource.clone()),
+            balance: Some(self.balance),
+            ..Default::default()
+        }
+    }
+}
+
+impl TransactionIntent for ActivateAccountParams {
+    fn contract_type(&self) -> &'static str {
+        "AccountCreateContract"
+    }
+
+    fn expected_value(&self) -> Value {
+        Value {
+            owner_address: self.owner_address.clone(),
+            account_address: Some(self.account_address.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::block::{BlockHeader, RawData as BlockRawData};
+    use crate::response::transaction::{Contract, Parameter, RawData};
+    use crate::signer::{PrivateKeySigner, Signer};
+
+    const OWNER: &str = "TRLpnm6Uz9s2Fcy3Q235k3SiAEBXGJCNq2";
+    const TO: &str = "TWsm8HtU2A5eEzoT8ev8yaoFjHsXLLrckb";
+
+    fn transfer(to: &str, amount: i64) -> Transaction {
+        let raw = RawData {
+            contract: vec![Contract {
+                parameter: Parameter {
+                    value: Value {
+                        owner_address: OWNER.into(),
+                        to_address: Some(to.into()),
+                        amount: Some(amount),
+                        ..Default::default()
+                    },
+                    type_url: "type.googleapis.com/protocol.TransferContract".into(),
+                },
+                type_field: "TransferContract".into(),
+                permission_id: None,
+            }],
+            ref_block_bytes: "1098".into(),
+            ref_block_hash: "0b5d4831c80cfdae".into(),
+            expiration: 1585881453000,
+            timestamp: 1585881393538,
+            ..Default::default()
+        };
+        Transaction::from_raw_data(raw, true).unwrap()
+    }
+
+    fn mismatch(res: Result<()>) -> Mismatch {
+        match res {
+            Err(Error::TransactionMismatch(m)) | Err(Error::BlockMismatch(m)) => m,
+            other => panic!("expected a mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_matching_transaction() {
+        let params = CreateTransactionParams::new(OWNER.into(), TO.into(), 1_000);
+        verify_transaction(&transfer(TO, 1_000), &params).unwrap();
+    }
+
+    #[test]
+    fn accepts_hex_params() {
+        let params = CreateTransactionParams::new(
+            "41a8a07f09def5e6a4462df90068c11abf6224e865".into(),
+            "41E552F6487585C2B58BC2C9BB4492BC1F17132CD0".into(),
+            1_000,
+        );
+        verify_transaction(&transfer(TO, 1_000), &params).unwrap();
+    }
+
+    #[test]
+    fn detects_swapped_hex() {
+        let params = CreateTransactionParams::new(OWNER.into(), TO.into(), 1_000);
+        let mut tx = transfer(TO, 1_000);
+        let evil = transfer(OWNER, 1_000_000);
+        tx.raw_data_hex = evil.raw_data_hex;
+        tx.tx_id = evil.tx_id;
+
+        match mismatch(verify_transaction(&tx, &params)) {
+            Mismatch::RawData { field, .. } => {
+                assert_eq!(field, "contract[0].parameter.value.amount")
+            }
+            m => panic!("unexpected mismatch {:?}", m),
+        }
+    }
+
+    #[test]
+    fn detects_wrong_intent() {
+        let params = CreateTransactionParams::new(OWNER.into(), TO.into(), 1_000);
+        match mismatch(verify_transaction(&transfer(OWNER, 1_000), &params)) {
+            Mismatch::Intent { field, .. } => assert_eq!(field, "to_address"),
+            m => panic!("unexpected mismatch {:?}", m),
+        }
+    }
+
+    #[test]
+    fn detects_bad_tx_id() {
+        let params = CreateTransactionParams::new(OWNER.into(), TO.into(), 1_000);
+        let mut tx = transfer(TO, 1_000);
+        tx.tx_id = "00".repeat(32);
+        assert!(matches!(
+            mismatch(verify_transaction(&tx, &params)),
+            Mismatch::TxId { .. }
+        ));
+    }
+
+    fn block_fixture() -> Block {
+        serde_json::from_str(include_str!("response/block.json")).unwrap()
+    }
+
+    fn signed_block(number: i64, parent_hash: &str) -> Block {
+        let witness = PrivateKeySigner::from_bytes(&[7u8; 32]).unwrap();
+        let transactions = vec![transfer(TO, number)];
+        let raw_data = BlockRawData {
+            number,
+            tx_trie_root: tx_trie_root(&transactions).unwrap(),
+            witness_address: witness.address().to_hex().unwrap(),
+            parent_hash: parent_hash.into(),
+            timestamp: 1585881396000 + number * 3000,
+            version: Some(30),
+            ..Default::default()
+        };
+        let raw = proto::encode_block_header_raw(&raw_data).unwrap();
+        let signature = witness.sign_hash(&Sha256::digest(&raw).into()).unwrap();
+        Block {
+            block_id: proto::block_id(number, &raw),
+            block_header: BlockHeader {
+                raw_data,
+                witness_signature: hex::encode(&signature[..]),
+            },
+            transactions,
+        }
+    }
+
+    #[test]
+    fn verifies_block() {
+        verify_block(&block_fixture()).unwrap();
+    }
+
+    #[test]
+    fn detects_tampered_block() {
+        let mut block = block_fixture();
+        block.transactions[0].ret.as_mut().unwrap()[0].contract_ret = "SUCCESS".into();
+        assert!(matches!(
+            mismatch(verify_block(&block)),
+            Mismatch::TxTrieRoot { .. }
+        ));
+
+        let mut block = block_fixture();
+        block.block_header.raw_data.timestamp += 3000;
+        assert!(matches!(
+            mismatch(verify_block(&block)),
+            Mismatch::BlockId { .. }
+        ));
+
+        let mut block = signed_block(10, &"00".repeat(32));
+        block.block_header.raw_data.witness_address = OWNER.into();
+        let raw = proto::encode_block_header_raw(&block.block_header.raw_data).unwrap();
+        block.block_id = proto::block_id(10, &raw);
+        assert!(matches!(
+            mismatch(verify_block(&block)),
+            Mismatch::Witness { .. }
+        ));
+    }
+
+    #[test]
+    fn detects_tampered_transaction_json() {
+        let mut block = block_fixture();
+        block.transactions[0].raw_data.expiration += 1;
+        match mismatch(verify_block(&block)) {
+            Mismatch::RawData { field, .. } => assert_eq!(field, "expiration"),
+            m => panic!("unexpected mismatch {:?}", m),
+        }
+    }
+
+    #[test]
+    fn verifies_unsigned_genesis() {
+        let mut genesis = signed_block(0, &"00".repeat(32));
+        genesis.block_header.witness_signature.clear();
+        verify_block(&genesis).unwrap();
+
+        // Mainnet block 0: a text as witness and a placeholder owner.
+        let mut genesis: Block =
+            serde_json::from_str(include_str!("response/genesis_block.json")).unwrap();
+        verify_block(&genesis).unwrap();
+        genesis.block_header.raw_data.parent_hash = "00".repeat(32);
+        assert!(matches!(
+            mismatch(verify_block(&genesis)),
+            Mismatch::BlockId { .. }
+        ));
+
+        let mut block = signed_block(10, &"00".repeat(32));
+        block.block_header.witness_signature.clear();
+        assert!(verify_block(&block).is_err());
+    }
+
+    #[test]
+    fn verifies_parent_hash_links() {
+        let first = signed_block(10, &"00".repeat(32));
+        let second = signed_block(11, &first.block_id);
+        let list = BlockList {
+            blocks: vec![first.clone(), second],
+        };
+        verify_block_list(&list).unwrap();
+
+        let list = BlockList {
+            blocks: vec![first, signed_block(11, &"11".repeat(32))],
+        };
+        assert!(matches!(
+            mismatch(verify_block_list(&list)),
+            Mismatch::ParentHash { number: 11, .. }
+        ));
+    }
+}
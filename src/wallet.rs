@@ -0,0 +1,413 @@
+//! Key generation and encrypted keystores.
+//!
+//! Keystores use the Web3 Secret Storage format (version 3) also used by
+//! geth and TronLink exports: the private key is encrypted with AES-128-CTR
+//! under a key derived from the password with scrypt or PBKDF2, and
+//! authenticated with `keccak256(derived_key[16..32] || ciphertext)`.
+
+use crate::address::Address;
+use crate::error::{Error, Result};
+use crate::signer::{PrivateKeySigner, Signer};
+use aes::Aes128;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::Hmac;
+use k256::ecdsa::SigningKey;
+use rand_core::{OsRng, RngCore};
+use serde_derive::{Deserialize, Serialize};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+pub use crate::signer::public_key_to_address;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+const CIPHER: &str = "aes-128-ctr";
+const PRF: &str = "hmac-sha256";
+const DKLEN: u32 = 32;
+
+// Ceilings on the cost a keystore file may ask for, far above what geth
+// and TronLink write, so a crafted file cannot hang or exhaust memory.
+// scrypt needs `128 * n * r` bytes, i.e. 1 GiB at the maximum.
+const MAX_SCRYPT_N: u32 = 1 << 20;
+const MAX_SCRYPT_R: u32 = 8;
+const MAX_SCRYPT_P: u32 = 16;
+const MAX_PBKDF2_C: u32 = 10_000_000;
+const MAX_DKLEN: u32 = 64;
+
+fn err<S: Into<String>>(msg: S) -> Error {
+    Error::Keystore(msg.into())
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut out = [0u8; N];
+    OsRng.fill_bytes(&mut out);
+    out
+}
+
+/// Generates a new random private key.
+pub fn generate() -> PrivateKeySigner {
+    PrivateKeySigner::from_signing_key(SigningKey::random(&mut OsRng))
+}
+
+/// Key derivation function and its parameters, as stored in the `kdf` and
+/// `kdfparams` fields of a keystore.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kdf", content = "kdfparams", rename_all = "lowercase")]
+pub enum Kdf {
+    Scrypt {
+        dklen: u32,
+        n: u32,
+        r: u32,
+        p: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        c: u32,
+        dklen: u32,
+        prf: String,
+        salt: String,
+    },
+}
+
+impl Kdf {
+    /// scrypt with the geth defaults (`n = 2^18, r = 8, p = 1`) and a
+    /// random salt.
+    pub fn scrypt() -> Kdf {
+        Kdf::scrypt_with(18, 8, 1)
+    }
+
+    /// scrypt with custom cost parameters and a random salt.
+    pub fn scrypt_with(log_n: u8, r: u32, p: u32) -> Kdf {
+        Kdf::Scrypt {
+            dklen: DKLEN,
+            n: 1 << log_n,
+            r,
+            p,
+            salt: hex::encode(random_bytes::<32>()),
+        }
+    }
+
+    /// PBKDF2-HMAC-SHA256 with `c` iterations and a random salt.
+    pub fn pbkdf2(c: u32) -> Kdf {
+        Kdf::Pbkdf2 {
+            c,
+            dklen: DKLEN,
+            prf: PRF.to_string(),
+            salt: hex::encode(random_bytes::<32>()),
+        }
+    }
+
+    fn derive(&self, password: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        match self {
+            Kdf::Scrypt {
+                dklen,
+                n,
+                r,
+                p,
+                salt,
+            } => {
+                if !n.is_power_of_two() {
+                    return Err(err(format!("scrypt n {} is not a power of two", n)));
+                }
+                if *n > MAX_SCRYPT_N || *r > MAX_SCRYPT_R || *p > MAX_SCRYPT_P {
+                    return Err(err(format!(
+                        "scrypt n {}, r {}, p {} exceed the limits of {}, {}, {}",
+                        n, r, p, MAX_SCRYPT_N, MAX_SCRYPT_R, MAX_SCRYPT_P
+                    )));
+                }
+                check_dklen(*dklen)?;
+                let salt = hex::decode(salt).map_err(|e| err(format!("salt: {}", e)))?;
+                let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p, *dklen as usize)
+                    .map_err(|e| err(e.to_string()))?;
+                let mut key = Zeroizing::new(vec![0u8; *dklen as usize]);
+                scrypt::scrypt(password, &salt, &params, &mut key)
+                    .map_err(|e| err(e.to_string()))?;
+                Ok(key)
+            }
+            Kdf::Pbkdf2 {
+                c,
+                dklen,
+                prf,
+                salt,
+            } => {
+                if prf != PRF {
+                    return Err(err(format!("unsupported prf {}", prf)));
+                }
+                if *c > MAX_PBKDF2_C {
+                    return Err(err(format!(
+                        "pbkdf2 c {} exceeds the limit of {}",
+                        c, MAX_PBKDF2_C
+                    )));
+                }
+                check_dklen(*dklen)?;
+                let salt = hex::decode(salt).map_err(|e| err(format!("salt: {}", e)))?;
+                let mut key = Zeroizing::new(vec![0u8; *dklen as usize]);
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(password, &salt, *c, &mut key)
+                    .map_err(|e| err(e.to_string()))?;
+                Ok(key)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Crypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    pub ciphertext: String,
+    #[serde(flatten)]
+    pub kdf: Kdf,
+    pub mac: String,
+}
+
+/// A Web3 Secret Storage (v3) keystore.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    /// Base58 address of the key. Optional in the format, always written by
+    /// this crate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub crypto: Crypto,
+    pub id: String,
+    pub version: u32,
+}
+
+fn check_dklen(dklen: u32) -> Result<()> {
+    if !(32..=MAX_DKLEN).contains(&dklen) {
+        return Err(err(format!("dklen must be between 32 and {}", MAX_DKLEN)));
+    }
+    Ok(())
+}
+
+fn mac(derived_key: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(&derived_key[16..32]);
+    hasher.update(ciphertext);
+    hasher.finalize().into()
+}
+
+fn uuid_v4() -> String {
+    let mut b = random_bytes::<16>();
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let h = hex::encode(b);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &h[..8],
+        &h[8..12],
+        &h[12..16],
+        &h[16..20],
+        &h[20..]
+    )
+}
+
+impl Keystore {
+    /// Encrypts `signer`'s private key with `password`.
+    pub fn encrypt(signer: &PrivateKeySigner, password: &str, kdf: Kdf) -> Result<Keystore> {
+        let derived_key = kdf.derive(password.as_bytes())?;
+
+        let iv = random_bytes::<16>();
+        let mut ciphertext = signer.signing_key().to_bytes().to_vec();
+        Aes128Ctr::new(derived_key[..16].into(), &iv.into()).apply_keystream(&mut ciphertext);
+
+        Ok(Keystore {
+            address: Some(signer.address().to_base58()?),
+            crypto: Crypto {
+                cipher: CIPHER.to_string(),
+                cipherparams: CipherParams {
+                    iv: hex::encode(iv),
+                },
+                ciphertext: hex::encode(&ciphertext),
+                kdf,
+                mac: hex::encode(mac(&derived_key, &ciphertext)),
+            },
+            id: uuid_v4(),
+            version: 3,
+        })
+    }
+
+    /// Decrypts the private key. Fails with [`Error::Keystore`] if the
+    /// password is wrong.
+    pub fn decrypt(&self, password: &str) -> Result<PrivateKeySigner> {
+        if self.version != 3 {
+            return Err(err(format!("unsupported version {}", self.version)));
+        }
+        if self.crypto.cipher != CIPHER {
+            return Err(err(format!("unsupported cipher {}", self.crypto.cipher)));
+        }
+
+        let derived_key = self.crypto.kdf.derive(password.as_bytes())?;
+        // Decrypted in place, so the buffer ends up holding the private key.
+        let mut ciphertext = Zeroizing::new(
+            hex::decode(&self.crypto.ciphertext).map_err(|e| err(format!("ciphertext: {}", e)))?,
+        );
+        let expected = hex::decode(&self.crypto.mac).map_err(|e| err(format!("mac: {}", e)))?;
+        if !bool::from(mac(&derived_key, &ciphertext)[..].ct_eq(&expected)) {
+            return Err(err("MAC mismatch (wrong password?)"));
+        }
+
+        let iv =
+            hex::decode(&self.crypto.cipherparams.iv).map_err(|e| err(format!("iv: {}", e)))?;
+        if iv.len() != 16 {
+            return Err(err("iv must be 16 bytes"));
+        }
+        Aes128Ctr::new(derived_key[..16].into(), iv[..].into())
+            .apply_keystream(&mut ciphertext[..]);
+        let signer = PrivateKeySigner::from_bytes(&ciphertext)?;
+
+        if let Some(address) = &self.address {
+            let stored: Address = address.parse()?;
+            if stored.to_bytes()? != signer.address().to_bytes()? {
+                return Err(err(format!("decrypted key does not belong to {}", address)));
+            }
+        }
+        Ok(signer)
+    }
+
+    /// Reads a keystore JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Keystore> {
+        let data = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Writes the keystore as JSON to a new file at `path`, readable only by
+    /// the owner on Unix. Fails if the file exists, so a key is never
+    /// overwritten.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let data = serde_json::to_string_pretty(self)?;
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(path).map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => err(format!("{} already exists", path.display())),
+            _ => e.into(),
+        })?;
+        file.write_all(data.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Reads and decrypts a keystore file.
+pub fn read_keystore<P: AsRef<Path>>(path: P, password: &str) -> Result<PrivateKeySigner> {
+    Keystore::load(path)?.decrypt(password)
+}
+
+/// Encrypts `signer` with scrypt defaults and writes it to a new file at
+/// `path`, see [`Keystore::save`].
+pub fn write_keystore<P: AsRef<Path>>(
+    path: P,
+    signer: &PrivateKeySigner,
+    password: &str,
+) -> Result<Keystore> {
+    let keystore = Keystore::encrypt(signer, password, Kdf::scrypt())?;
+    keystore.save(path)?;
+    Ok(keystore)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector from the Web3 Secret Storage definition.
+    #[test]
+    fn decrypts_pbkdf2_test_vector() {
+        let keystore: Keystore = serde_json::from_str(
+            r#"{
+                "crypto" : {
+                    "cipher" : "aes-128-ctr",
+                    "cipherparams" : { "iv" : "6087dab2f9fdbbfaddc31a909735c1e6" },
+                    "ciphertext" : "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+                    "kdf" : "pbkdf2",
+                    "kdfparams" : {
+                        "c" : 262144,
+                        "dklen" : 32,
+                        "prf" : "hmac-sha256",
+                        "salt" : "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+                    },
+                    "mac" : "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+                },
+                "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+                "version" : 3
+            }"#,
+        )
+        .unwrap();
+
+        let signer = keystore.decrypt("testpassword").unwrap();
+        assert_eq!(
+            hex::encode(signer.signing_key().to_bytes()),
+            "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d"
+        );
+        assert!(keystore.decrypt("wrong").is_err());
+    }
+
+    #[test]
+    fn round_trips_scrypt() {
+        let signer = generate();
+        let keystore = Keystore::encrypt(&signer, "secret", Kdf::scrypt_with(10, 8, 1)).unwrap();
+        let json = serde_json::to_string(&keystore).unwrap();
+        assert!(json.contains(r#""kdf":"scrypt""#));
+
+        let keystore: Keystore = serde_json::from_str(&json).unwrap();
+        let decrypted = keystore.decrypt("secret").unwrap();
+        assert_eq!(decrypted.address(), signer.address());
+        assert!(keystore.decrypt("wrong").is_err());
+    }
+
+    #[test]
+    fn rejects_costly_kdf_params() {
+        let signer = generate();
+        let keystore = Keystore::encrypt(&signer, "secret", Kdf::scrypt_with(10, 8, 1)).unwrap();
+        for kdf in [
+            Kdf::scrypt_with(30, 8, 1),
+            Kdf::scrypt_with(10, 1024, 1),
+            Kdf::scrypt_with(10, 8, 1 << 20),
+            Kdf::pbkdf2(u32::MAX),
+            Kdf::Pbkdf2 {
+                c: 1,
+                dklen: u32::MAX,
+                prf: PRF.to_string(),
+                salt: String::new(),
+            },
+        ] {
+            let mut crafted = keystore.clone();
+            crafted.crypto.kdf = kdf;
+            assert!(matches!(crafted.decrypt("secret"), Err(Error::Keystore(_))));
+        }
+    }
+
+    #[test]
+    fn saves_without_overwriting() {
+        let signer = generate();
+        let keystore = Keystore::encrypt(&signer, "secret", Kdf::scrypt_with(10, 8, 1)).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("tron-api-client-keystore-{}", std::process::id()));
+        keystore.save(&path).unwrap();
+        assert!(matches!(keystore.save(&path), Err(Error::Keystore(_))));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let loaded = Keystore::load(&path).unwrap();
+        assert_eq!(
+            loaded.decrypt("secret").unwrap().address(),
+            signer.address()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
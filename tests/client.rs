@@ -2,23 +2,213 @@
 // use lazy_static::lazy_static;
 // use tokio::sync::{Mutex, MutexGuard};
 
-use tron_api_client::{Address, Client, TxId, client::Network};
+use tron_api_client::response::transaction::BroadcastCode;
+use std::time::{Duration, Instant};
+use tron_api_client::client::Network;
+use tron_api_client::error::Error;
+use tron_api_client::retry::RetryPolicy;
+use tron_api_client::{Address, Client, PrivateKeySigner, Signer, TxId};
 use log::info;
 // mod data;
 
 // use data::*;
 
+// These talk to TronGrid; run them with `cargo test -- --ignored`.
 fn get_client() -> Client {
-    let client = Client::for_shasta(None);
-    client
+    Client::for_shasta(None)
 }
 
 fn get_client_main() -> Client {
-    let client = Client::for_main(None);
-    client
+    Client::for_main(None)
+}
+
+#[test]
+fn client_builder_rejects_invalid_config() {
+    assert!(Client::builder().base_url("not a url").build().is_err());
+    assert!(Client::try_new("not a url".into(), None).is_err());
+    assert!(Client::builder()
+        .header("bad header", "value")
+        .build()
+        .is_err());
+    assert!(Client::builder()
+        .http_client(reqwest::Client::new())
+        .proxy("http://127.0.0.1:3128")
+        .build()
+        .is_err());
+
+    Client::builder()
+        .network(Network::Nile)
+        .api_key("key")
+        .connect_timeout(Duration::from_secs(5))
+        .timeout(Duration::from_secs(30))
+        .proxy("http://127.0.0.1:3128")
+        .user_agent("tron-api-client-tests")
+        .header("X-Request-Source", "tests")
+        .build()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn client_builder_applies_timeout() {
+    // The listener accepts connections but never answers, so only the
+    // timeout can end the request.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut sockets = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            sockets.push(socket);
+        }
+    });
+
+    let client = Client::builder()
+        .base_url(&url)
+        .timeout(Duration::from_millis(200))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    let started = Instant::now();
+    match client.get_node_info().await {
+        Err(Error::HTTP(e)) => assert!(e.is_timeout(), "{}", e),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+/// Serves `responses` (status, body) to consecutive connections on a local
+/// port and returns the base URL.
+async fn serve(responses: Vec<(u16, String)>) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        for (status, body) in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 16384];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let res = format!(
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(res.as_bytes()).await.unwrap();
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn retries_transient_failures_and_empty_responses() {
+    let tx = r#"{"txID":"ab","raw_data":{"contract":[],"ref_block_bytes":"","ref_block_hash":"","expiration":0},"raw_data_hex":""}"#;
+    let url = serve(vec![
+        (503, "".into()),
+        (200, "{}".into()),
+        (200, tx.into()),
+    ])
+    .await;
+    let client = Client::builder()
+        .base_url(url)
+        .retry_policy(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+
+    let res = client.get_transaction_by_id(TxId("ab".into())).await.unwrap();
+    assert_eq!(res.tx_id, "ab");
+}
+
+#[tokio::test]
+async fn rotates_rejected_api_keys() {
+    let tx = r#"{"txID":"ab","raw_data":{"contract":[],"ref_block_bytes":"","ref_block_hash":"","expiration":0},"raw_data_hex":""}"#;
+    let url = serve(vec![(429, "".into()), (200, tx.into())]).await;
+    let client = Client::builder()
+        .base_url(url)
+        .api_keys(vec!["first-api-key", "second-api-key"])
+        .retry_policy(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+
+    client.get_transaction_by_id(TxId("ab".into())).await.unwrap();
+    let stats = client.api_key_stats();
+    assert_eq!((stats[0].requests, stats[0].rejections), (1, 1));
+    assert!(stats[0].exhausted);
+    assert_eq!((stats[1].requests, stats[1].rejections), (1, 0));
+}
+
+fn block_at(number: i64) -> String {
+    format!(
+        r#"{{"blockID":"","block_header":{{"raw_data":{{"number":{},"txTrieRoot":"","witness_address":"","parentHash":"","timestamp":0}},"witness_signature":""}}}}"#,
+        number
+    )
+}
+
+#[tokio::test]
+async fn fails_over_to_next_endpoint() {
+    // A port nothing listens on any more.
+    let closed = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let url = serve(vec![(200, block_at(7))]).await;
+    let client = Client::builder()
+        .base_url(closed)
+        .endpoint(url)
+        .retry_policy(RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+
+    let block = client.get_now_block().await.unwrap();
+    assert_eq!(block.block_header.raw_data.number, 7);
+}
+
+#[tokio::test]
+async fn excludes_lagging_endpoints() {
+    let synced = serve(vec![(200, block_at(1000)), (200, block_at(980)), (200, block_at(1001))]).await;
+    let lagging = serve(vec![(200, block_at(900)), (200, block_at(880))]).await;
+    let client = Client::builder()
+        .base_url(lagging)
+        .endpoint(synced)
+        .max_block_lag(20)
+        .build()
+        .unwrap();
+
+    let health = client.check_health().await;
+    assert!(!health[0].healthy);
+    assert!(health[1].healthy);
+    assert_eq!(health[1].solidity_block, Some(980));
+
+    // The lagging endpoint comes first but is skipped.
+    let block = client.get_now_block().await.unwrap();
+    assert_eq!(block.block_header.raw_data.number, 1001);
 }
 
 #[tokio::test]
+async fn does_not_retry_broadcasts() {
+    let url = serve(vec![
+        (503, "".into()),
+        (200, r#"{"result":true}"#.into()),
+    ])
+    .await;
+    let client = Client::builder().base_url(url).build().unwrap();
+
+    assert!(client
+        .broadcast_hex("00".into())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+#[ignore]
 async fn get_node_info() {
     let client = get_client();
 
@@ -30,6 +220,7 @@ async fn get_node_info() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn node_list() {
     let client = get_client();
 
@@ -38,6 +229,7 @@ async fn node_list() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn list_witnesses() {
     let client = get_client();
 
@@ -49,10 +241,11 @@ async fn list_witnesses() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_block_by_num() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_block_by_num(10)
         .await
         .expect("Error fetching block by num");
@@ -60,10 +253,11 @@ async fn get_block_by_num() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_block_by_latest_num() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_block_by_latest_num(3)
         .await
         .expect("Error fetching num latest blocks");
@@ -71,6 +265,7 @@ async fn get_block_by_latest_num() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_block_by_limit_next() {
     let client = get_client();
 
@@ -82,10 +277,11 @@ async fn get_block_by_limit_next() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_block_by_num_with_transactions() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_block_by_num(3412121)
         .await
         .expect("Error fetching block by num");
@@ -93,20 +289,22 @@ async fn get_block_by_num_with_transactions() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_block_by_id() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_block_by_id("000000000000000a4efe701d7a03ff578104c6c1995ab70e713c30318b266e90")
         .await
         .expect("Error fetching block by id");
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_account() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_account(Address::Hex(
             "41E552F6487585C2B58BC2C9BB4492BC1F17132CD0".into(),
         ))
@@ -115,10 +313,11 @@ async fn get_account() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_account_2() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_account(Address::Hex(
             "41a8a07f09def5e6a4462df90068c11abf6224e865".into(),
         ))
@@ -127,10 +326,11 @@ async fn get_account_2() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_account_net() {
     let client = get_client_main();
 
-    let info = client
+    let _info = client
         .get_account_net(Address::Hex(
             "41E552F6487585C2B58BC2C9BB4492BC1F17132CD0".into(),
         ))
@@ -139,10 +339,11 @@ async fn get_account_net() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_account_net2() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_account_net(Address::Hex(
             "41a8a07f09def5e6a4462df90068c11abf6224e865".into(),
         ))
@@ -151,20 +352,22 @@ async fn get_account_net2() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_account_2_base58() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_account(Address::Base58("TRLpnm6Uz9s2Fcy3Q235k3SiAEBXGJCNq2".into()))
         .await
         .expect("Error fetching account");
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_transaction_by_id() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_transaction_by_id(TxId(
             "809e9d9aa5381f32f748618e4d592a58542e21fe794f35959ce811f2a58fc969".into(),
         ))
@@ -173,10 +376,11 @@ async fn get_transaction_by_id() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_transaction_info_by_id() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_transaction_info_by_id(TxId(
             "809e9d9aa5381f32f748618e4d592a58542e21fe794f35959ce811f2a58fc969".into(),
         ))
@@ -185,20 +389,22 @@ async fn get_transaction_info_by_id() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_now_block() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_now_block()
         .await
         .expect("Error fetching now block");
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_chain_parameters() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_chain_parameters()
         .await
         .expect("Error fetching chain parameters");
@@ -207,10 +413,11 @@ async fn get_chain_parameters() {
 // contract 417ca2c40d9aa986b6608e07a68ebf33ea5f19a866
 
 #[tokio::test]
+#[ignore]
 async fn get_contract() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_contract(Address::Hex(
             "417ca2c40d9aa986b6608e07a68ebf33ea5f19a866".into(),
         ))
@@ -219,19 +426,21 @@ async fn get_contract() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn get_asset_issue_list() {
     let client = get_client();
 
-    let info = client
+    let _info = client
         .get_asset_issue_list()
         .await
         .expect("Error fetching asset issue list");
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_get_contract_transfer_events() {
-    env_logger::init();
-    let client = Client::for_main(None);
+    let _ = env_logger::try_init();
+    let client = get_client_main();
     
     // USDT contract address on mainnet
     let contract = "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t";
@@ -286,8 +495,9 @@ async fn test_get_contract_transfer_events() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_get_contract_transfer_events_counts() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
 
     for block_number in 67963911..67963912 {
@@ -307,8 +517,9 @@ async fn test_get_contract_transfer_events_counts() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_trigger_constant_contract() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
     
     let owner_address = "TNXoiAJ3dct8Fjg4M9fkLFh9S2v9TXc32G";  // Example address
@@ -328,8 +539,9 @@ async fn test_trigger_constant_contract() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_estimate_energy() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
     
     // Test contract parameters
@@ -373,8 +585,9 @@ async fn test_estimate_energy() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_create_transaction() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
     
     let owner_address = "TZ4UXDV5ZhNW7fb2AMSbgfAEZ7hWsnYS2g";
@@ -402,8 +615,9 @@ async fn test_create_transaction() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_broadcast_hex() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
     
     // First create a transaction
@@ -447,8 +661,37 @@ async fn test_broadcast_hex() {
 }
 
 #[tokio::test]
+#[ignore]
+async fn test_broadcast_transaction() {
+    let _ = env_logger::try_init();
+    let client = get_client_main();
+
+    let owner_address = "TZ4UXDV5ZhNW7fb2AMSbgfAEZ7hWsnYS2g";
+    let to_address = "TPswDDCAWhJAZGdHPidFg5nEf8TkNToDX1";
+    let amount = 1000;
+
+    let mut tx = client
+        .create_transaction(owner_address, to_address, amount)
+        .await
+        .unwrap();
+
+    // Signed with a key that does not own the account, so the node must
+    // parse the JSON and then reject the signature.
+    let signer: PrivateKeySigner = "01".repeat(32).parse().unwrap();
+    signer.sign_transaction(&mut tx).unwrap();
+
+    let response = client.broadcast_transaction(&tx).await.unwrap();
+    info!("Broadcast result: {:?} {:?}", response, response.message_text());
+
+    assert!(!response.result);
+    assert_eq!(response.code, Some(BroadcastCode::Sigerror));
+    assert_eq!(response.txid.as_deref(), Some(tx.tx_id.as_str()));
+}
+
+#[tokio::test]
+#[ignore]
 async fn test_unfreeze_balance_v2() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
     
     let owner_address = "TZ4UXDV5ZhNW7fb2AMSbgfAEZ7hWsnYS2g";
@@ -481,8 +724,9 @@ async fn test_unfreeze_balance_v2() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_delegate_resource() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
     
     let owner_address = "TZ4UXDV5ZhNW7fb2AMSbgfAEZ7hWsnYS2g";
@@ -526,8 +770,9 @@ async fn test_delegate_resource() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_undelegate_resource() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
     
     let owner_address = "TZ4UXDV5ZhNW7fb2AMSbgfAEZ7hWsnYS2g";
@@ -569,8 +814,9 @@ async fn test_undelegate_resource() {
 }
 
 #[tokio::test]
+#[ignore]
 async fn test_activate_account() {
-    env_logger::init();
+    let _ = env_logger::try_init();
     let client = get_client_main();
     
     let owner_address = "TZ4UXDV5ZhNW7fb2AMSbgfAEZ7hWsnYS2g";  // Already activated account
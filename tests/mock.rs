@@ -0,0 +1,704 @@
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tron_api_client::auth::{Auth, TlsConfig};
+use tron_api_client::cache::ResponseCache;
+use tron_api_client::cassette::CassetteTransport;
+use tron_api_client::error::Error;
+use tron_api_client::network::{Network, NetworkConfig};
+use tron_api_client::rate_limit::{EndpointClass, RateLimit, RateLimiter};
+use tron_api_client::response::transaction::BroadcastCode;
+use tron_api_client::retry::RetryPolicy;
+use tron_api_client::transport::{
+    MockTransport, Transport, TransportError, TransportFuture, TransportRequest, TransportResponse,
+};
+use tron_api_client::{Address, Client, Confirmation, TxId};
+
+const BLOCK: &str = include_str!("../src/response/block.json");
+// Mainnet block 0, rebuilt from the genesis config java-tron ships; its
+// header hashes to the mainnet genesis ID.
+const GENESIS_BLOCK: &str = include_str!("../src/response/genesis_block.json");
+const CHAIN_PARAMETERS: &str = include_str!("../src/response/chain_parameters.json");
+const NODE_LIST: &str = include_str!("../src/response/node_list.json");
+const WITNESS_LIST: &str = include_str!("../src/response/witness_list.json");
+const TRANSACTION_INFO: &str = include_str!("../src/response/transaction_info.json");
+const CONTRACT: &str = include_str!("../src/response/tron_contract.json");
+
+fn mock_client() -> (Arc<MockTransport>, Client) {
+    let mock = Arc::new(MockTransport::new());
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .transport(mock.clone())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    (mock, client)
+}
+
+/// First sample of a fixture holding an array of samples.
+fn first(fixture: &str) -> Value {
+    match serde_json::from_str(fixture).unwrap() {
+        Value::Array(mut samples) => samples.remove(0),
+        sample => sample,
+    }
+}
+
+#[tokio::test]
+async fn get_block_by_num() {
+    let (mock, client) = mock_client();
+    mock.respond("POST", "/wallet/getblockbynum", 200, BLOCK);
+
+    let block = client.get_block_by_num(1_000).await.unwrap();
+    assert!(!block.block_id.is_empty());
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].url.as_str(),
+        "http://node.test/wallet/getblockbynum"
+    );
+    assert_eq!(requests[0].body, Some(json!({ "num": 1000 })));
+}
+
+#[tokio::test]
+async fn get_queries() {
+    let (mock, client) = mock_client();
+    mock.respond("POST", "/wallet/getnowblock", 200, BLOCK);
+    mock.respond_json(
+        "GET",
+        "/wallet/getchainparameters",
+        &first(CHAIN_PARAMETERS),
+    );
+    mock.respond_json("GET", "/wallet/listnodes", &first(NODE_LIST));
+    mock.respond_json("GET", "/walletsolidity/listwitnesses", &first(WITNESS_LIST));
+
+    client.get_now_block().await.unwrap();
+    client.get_chain_parameters().await.unwrap();
+    client.list_nodes().await.unwrap();
+    client.list_witnesses().await.unwrap();
+
+    let paths: Vec<String> = mock.requests().iter().map(|r| r.path()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "/wallet/getnowblock",
+            "/wallet/getchainparameters",
+            "/wallet/listnodes",
+            "/walletsolidity/listwitnesses",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn get_transaction_info_and_contract() {
+    let (mock, client) = mock_client();
+    let info = first(TRANSACTION_INFO);
+    mock.respond_json("POST", "/wallet/gettransactioninfobyid", &info);
+    mock.respond_json("POST", "/wallet/getcontract", &first(CONTRACT));
+
+    let id = info["id"].as_str().unwrap().to_string();
+    let res = client
+        .get_transaction_info_by_id(TxId(id.clone()))
+        .await
+        .unwrap();
+    assert_eq!(res.id, id);
+
+    let address: Address = "TEEXEWrkMFKapSMJ6mErg39ELFKDqEs6w3".parse().unwrap();
+    client.get_contract(address).await.unwrap();
+    assert_eq!(mock.requests()[0].body, Some(json!({ "value": id })));
+}
+
+#[tokio::test]
+async fn reports_server_errors() {
+    let (mock, client) = mock_client();
+    mock.respond(
+        "POST",
+        "/wallet/broadcasttransaction",
+        200,
+        r#"{"code":"SIGERROR","txid":"ab","message":"76616c6964617465207369676e6174757265206572726f72"}"#,
+    );
+    let tx = serde_json::from_str(
+        r#"{"txID":"ab","raw_data":{"contract":[],"ref_block_bytes":"","ref_block_hash":"","expiration":0},"raw_data_hex":""}"#,
+    )
+    .unwrap();
+
+    let res = client.broadcast_transaction(&tx).await.unwrap();
+    assert_eq!(res.code, Some(BroadcastCode::Sigerror));
+    assert_eq!(res.message_text().unwrap(), "validate signature error");
+}
+
+#[tokio::test]
+async fn fails_over_on_transport_errors() {
+    let mock = Arc::new(MockTransport::new());
+    mock.respond_with("POST", "/wallet/getnowblock", |req| {
+        if req.url.host_str() == Some("down.test") {
+            Err(TransportError::Connect("refused".into()))
+        } else {
+            Ok(TransportResponse::new(200, BLOCK))
+        }
+    });
+    let client = Client::builder()
+        .base_url("http://down.test")
+        .endpoint("http://up.test")
+        .transport(mock.clone())
+        .retry_policy(RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+
+    client.get_now_block().await.unwrap();
+    client.get_now_block().await.unwrap();
+    let hosts: Vec<_> = mock
+        .requests()
+        .iter()
+        .map(|r| r.url.host_str().unwrap().to_string())
+        .collect();
+    // The failed endpoint is avoided after its first failure.
+    assert_eq!(hosts, vec!["down.test", "up.test", "up.test"]);
+
+    // Broadcasts do not fail over, nor does a client without retries.
+    mock.fail("POST", "/wallet/broadcasthex");
+    let client = |retry_policy| {
+        Client::builder()
+            .base_url("http://down.test")
+            .endpoint("http://up.test")
+            .transport(mock.clone())
+            .retry_policy(retry_policy)
+            .build()
+            .unwrap()
+    };
+    let no_retries = RetryPolicy {
+        max_retries: 0,
+        ..Default::default()
+    };
+    assert!(client(no_retries).broadcast_hex("00".into()).await.is_err());
+    assert!(client(RetryPolicy::none()).get_now_block().await.is_err());
+    let hosts: Vec<_> = mock.requests()[3..]
+        .iter()
+        .map(|r| r.url.host_str().unwrap().to_string())
+        .collect();
+    assert_eq!(hosts, vec!["down.test", "down.test"]);
+
+    let (mock, client) = mock_client();
+    mock.fail("GET", "/wallet/getnodeinfo");
+    assert!(client.get_node_info().await.is_err());
+}
+
+#[tokio::test]
+async fn keeps_service_nodes_apart_from_failover() {
+    let mock = Arc::new(MockTransport::new());
+    mock.respond("POST", "/wallet/getnowblock", 200, solid_head(3_412_121));
+    mock.respond_with("POST", "/walletsolidity/getnowblock", |req| {
+        if req.url.host_str() == Some("solidity.test") {
+            Err(TransportError::Connect("refused".into()))
+        } else {
+            Ok(TransportResponse::new(200, solid_head(3_412_100)))
+        }
+    });
+    let client = Client::builder()
+        .base_url("http://node0.test")
+        .endpoint("http://node1.test")
+        .solidity_url("http://solidity.test")
+        .transport(mock.clone())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    // The dead solidity node is tried once, and the full nodes stay in use.
+    assert!(client.get_now_block_solidity().await.is_err());
+    client.get_now_block().await.unwrap();
+    let urls: Vec<String> = mock.requests().iter().map(|r| r.url.to_string()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "http://solidity.test/walletsolidity/getnowblock",
+            "http://node0.test/wallet/getnowblock"
+        ]
+    );
+
+    // The solidity node gets its own health entry and is not probed
+    // through the full nodes.
+    let health = client.check_health().await;
+    let hosts: Vec<_> = health.iter().map(|h| h.url.host_str().unwrap()).collect();
+    assert_eq!(hosts, vec!["node0.test", "node1.test", "solidity.test"]);
+    assert!(health[0].healthy && health[1].healthy && !health[2].healthy);
+    assert_eq!(health[0].solidity_block, None);
+}
+
+#[tokio::test]
+async fn replays_recorded_session() {
+    let path = std::env::temp_dir().join(format!(
+        "tron-api-client-session-{}.json",
+        std::process::id()
+    ));
+    let (mock, _) = mock_client();
+    mock.respond("POST", "/wallet/getblockbynum", 200, BLOCK);
+    let client = |transport: CassetteTransport| {
+        Client::builder()
+            .base_url("http://node.test")
+            .transport(Arc::new(transport))
+            .build()
+            .unwrap()
+    };
+
+    let recorded = client(CassetteTransport::record(&path, mock))
+        .get_block_by_num(1_000)
+        .await
+        .unwrap();
+    let replayed = client(CassetteTransport::replay(&path).unwrap())
+        .get_block_by_num(1_000)
+        .await
+        .unwrap();
+    assert_eq!(recorded.block_id, replayed.block_id);
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn solid_head(number: i64) -> String {
+    format!(
+        r#"{{"blockID":"","block_header":{{"raw_data":{{"number":{},"txTrieRoot":"","witness_address":"","parentHash":"","timestamp":0}},"witness_signature":""}}}}"#,
+        number
+    )
+}
+
+#[tokio::test]
+async fn caches_solidified_blocks_only() {
+    let mock = Arc::new(MockTransport::new());
+    mock.respond("POST", "/wallet/getblockbynum", 200, BLOCK);
+    mock.respond(
+        "POST",
+        "/walletsolidity/getnowblock",
+        200,
+        solid_head(3_412_000),
+    );
+    mock.respond(
+        "POST",
+        "/walletsolidity/getnowblock",
+        200,
+        solid_head(3_412_200),
+    );
+    let cache = Arc::new(ResponseCache::memory(16));
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .transport(mock.clone())
+        .cache(cache.clone())
+        .build()
+        .unwrap();
+
+    // Block 3412121 is above the solidified head and refetched every time.
+    client.get_block_by_num(3_412_121).await.unwrap();
+    client.get_block_by_num(3_412_121).await.unwrap();
+    assert_eq!(client.cache_stats().unwrap().stores, 0);
+
+    // Once the solidified head passes it, the solidified copy is fetched,
+    // replacing the one read from `/wallet` which may be on a fork, and
+    // cached.
+    mock.respond(
+        "POST",
+        "/walletsolidity/getblockbynum",
+        200,
+        solid_head(3_412_121),
+    );
+    client.get_now_block_solidity().await.unwrap();
+    let block = client.get_block_by_num(3_412_121).await.unwrap();
+    assert_eq!(block.block_id, "");
+    let block = client.get_block_by_num(3_412_121).await.unwrap();
+    assert_eq!(block.block_id, "");
+
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.stores), (1, 3, 1));
+    let fetches = mock
+        .requests()
+        .iter()
+        .filter(|r| r.path() == "/wallet/getblockbynum")
+        .count();
+    assert_eq!(fetches, 3);
+
+    // Contracts can change, e.g. by ClearABIContract, and are not cached.
+    mock.respond_json("POST", "/wallet/getcontract", &first(CONTRACT));
+    let address: Address = "TEEXEWrkMFKapSMJ6mErg39ELFKDqEs6w3".parse().unwrap();
+    client.get_contract(address.clone()).await.unwrap();
+    client.get_contract(address).await.unwrap();
+    assert_eq!(cache.stats().stores, 1);
+}
+
+#[tokio::test]
+async fn routes_by_confirmation() {
+    let mock = Arc::new(MockTransport::new());
+    mock.respond("POST", "/walletsolidity/getblockbynum", 200, BLOCK);
+    mock.respond("POST", "/walletpbft/getnowblock", 200, BLOCK);
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .transport(mock.clone())
+        .confirmation(Confirmation::Solidified)
+        .build()
+        .unwrap();
+
+    client.get_block_by_num(3_412_121).await.unwrap();
+    client
+        .with_confirmation(Confirmation::Pbft)
+        .get_now_block()
+        .await
+        .unwrap();
+
+    let address: Address = "TEEXEWrkMFKapSMJ6mErg39ELFKDqEs6w3".parse().unwrap();
+    match client.get_account_net(address).await {
+        Err(Error::UnsupportedConfirmation(_)) => {}
+        other => panic!("expected UnsupportedConfirmation, got {:?}", other),
+    }
+
+    let paths: Vec<String> = mock.requests().iter().map(|r| r.path()).collect();
+    assert_eq!(
+        paths,
+        vec!["/walletsolidity/getblockbynum", "/walletpbft/getnowblock"]
+    );
+}
+
+#[tokio::test]
+async fn uses_custom_network() {
+    let configs = NetworkConfig::parse_list(
+        r#"[{
+            "name": "private",
+            "fullnode_url": "http://fullnode.test",
+            "solidity_url": "http://solidity.test",
+            "event_url": "http://events.test",
+            "genesis_block_id": "00000000000000001ebf88508a03865c71d452e25f4d51194196a1d22b6653dc"
+        }]"#,
+    )
+    .unwrap();
+    let network = Network::parse_with("private", &configs).unwrap();
+    let mock = Arc::new(MockTransport::new());
+    mock.respond("POST", "/wallet/getblockbynum", 200, GENESIS_BLOCK);
+    mock.respond_json("GET", "/walletsolidity/listwitnesses", &first(WITNESS_LIST));
+    let client = Client::builder()
+        .network(network)
+        .transport(mock.clone())
+        .build()
+        .unwrap();
+
+    client.verify_chain().await.unwrap();
+    client.list_witnesses().await.unwrap();
+
+    let urls: Vec<String> = mock.requests().iter().map(|r| r.url.to_string()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "http://fullnode.test/wallet/getblockbynum",
+            "http://solidity.test/walletsolidity/listwitnesses"
+        ]
+    );
+
+    let client = Client::builder()
+        .network(Network::Main)
+        .transport(mock.clone())
+        .build()
+        .unwrap();
+    client.verify_chain().await.unwrap();
+
+    let client = Client::builder()
+        .network(Network::Nile)
+        .transport(mock)
+        .build()
+        .unwrap();
+    match client.verify_chain().await {
+        Err(Error::WrongChain(_)) => {}
+        other => panic!("expected WrongChain, got {:?}", other),
+    }
+
+    // A node claiming the expected ID for a block with another header.
+    let forged = GENESIS_BLOCK.replace(
+        "00000000000000001ebf88508a03865c71d452e25f4d51194196a1d22b6653dc",
+        Network::Nile.genesis_block_id().unwrap(),
+    );
+    let mock = Arc::new(MockTransport::new());
+    mock.respond("POST", "/wallet/getblockbynum", 200, forged);
+    let client = Client::builder()
+        .network(Network::Nile)
+        .transport(mock)
+        .build()
+        .unwrap();
+    match client.verify_chain().await {
+        Err(Error::BlockMismatch(_)) => {}
+        other => panic!("expected BlockMismatch, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn authenticates_every_request() {
+    let mock = Arc::new(MockTransport::new());
+    mock.respond("POST", "/wallet/getblockbynum", 200, BLOCK);
+    mock.respond_json("GET", "/walletsolidity/listwitnesses", &first(WITNESS_LIST));
+    let events = "/v1/contracts/TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t/events";
+    mock.respond("GET", events, 200, r#"{"data": [], "success": true}"#);
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .solidity_url("http://solidity.test")
+        .event_url("http://events.test")
+        .auth(Auth::basic("tron", Some("secret".into())))
+        .transport(mock.clone())
+        .build()
+        .unwrap();
+
+    client.get_block_by_num(1).await.unwrap();
+    client.list_witnesses().await.unwrap();
+    client
+        .get_contract_events::<Value>(
+            "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t",
+            "Transfer",
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 3);
+    for request in &requests {
+        assert_eq!(
+            request.headers["authorization"], "Basic dHJvbjpzZWNyZXQ=",
+            "{}",
+            request.url
+        );
+    }
+
+    let tls = Client::builder()
+        .transport(mock)
+        .tls(TlsConfig::new())
+        .build();
+    assert!(matches!(tls, Err(Error::InvalidConfig(_))));
+}
+
+/// Answers `getblockbynum` after a delay, tracking requests in flight.
+#[derive(Debug, Default)]
+struct SlowNode {
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl Transport for SlowNode {
+    fn send(&self, request: TransportRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(current, Ordering::SeqCst);
+            let num = request.body.unwrap()["num"].as_i64().unwrap();
+            // Later blocks answer first.
+            tokio::time::sleep(Duration::from_millis(50 - 5 * num as u64)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            let body = if num == 3 {
+                "{}".into()
+            } else {
+                solid_head(num)
+            };
+            Ok(TransportResponse::new(200, body))
+        })
+    }
+}
+
+#[tokio::test]
+async fn fetches_in_bulk_in_input_order() {
+    let node = Arc::new(SlowNode::default());
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .transport(node.clone())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let blocks = client.get_blocks_by_num(1..=8, 3).await;
+    let numbers: Vec<Option<i64>> = blocks
+        .iter()
+        .map(|b| b.as_ref().ok().map(|b| b.block_header.raw_data.number))
+        .collect();
+    assert_eq!(
+        numbers,
+        vec![
+            Some(1),
+            Some(2),
+            None,
+            Some(4),
+            Some(5),
+            Some(6),
+            Some(7),
+            Some(8)
+        ]
+    );
+    assert_eq!(node.max_in_flight.load(Ordering::SeqCst), 3);
+}
+
+#[cfg(feature = "prometheus")]
+#[tokio::test]
+async fn exports_prometheus_metrics() {
+    let handle = tron_api_client::telemetry::install_prometheus().unwrap();
+    let (mock, _) = mock_client();
+    mock.respond("GET", "/wallet/getnodeinfo", 503, "");
+    mock.respond("GET", "/wallet/getnodeinfo", 200, "{}");
+    let client = Client::builder()
+        .base_url("http://metrics.test")
+        .transport(mock)
+        .retry_policy(RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let _ = client.get_node_info().await;
+
+    let text = handle.render();
+    assert!(text
+        .contains(r#"tron_client_requests_total{endpoint="/wallet/getnodeinfo",status="200"} 1"#));
+    assert!(text
+        .contains(r#"tron_client_errors_total{endpoint="/wallet/getnodeinfo",kind="status"} 1"#));
+    assert!(text.contains(r#"tron_client_retries_total{endpoint="/wallet/getnodeinfo"} 1"#));
+    assert!(text.contains("tron_client_request_duration_seconds_bucket"));
+}
+
+#[tokio::test]
+async fn maps_error_statuses() {
+    let mock = Arc::new(MockTransport::new());
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .transport(mock.clone())
+        .retry_policy(RetryPolicy::none())
+        .api_key("api-key")
+        .build()
+        .unwrap();
+    mock.respond(
+        "GET",
+        "/wallet/getnodeinfo",
+        401,
+        r#"{"Success":false,"Error":"ApiKey not exists","StatusCode":401}"#,
+    );
+    mock.respond(
+        "GET",
+        "/wallet/listnodes",
+        403,
+        r#"{"Success":false,"Error":"The key exceeds the frequency limit(15), and the query server is suspended for 30s","StatusCode":403}"#,
+    );
+    mock.respond("GET", "/wallet/getchainparameters", 500, "x".repeat(2000));
+
+    assert!(matches!(
+        client.get_node_info().await,
+        Err(Error::InvalidAPIKey)
+    ));
+    match client.list_nodes().await {
+        Err(Error::RateLimited(e)) => assert_eq!(e.status, 403),
+        other => panic!("expected RateLimited, got {:?}", other),
+    }
+    match client.get_chain_parameters().await {
+        Err(Error::HttpStatus(e)) => {
+            assert_eq!(
+                (e.status, e.path.as_str()),
+                (500, "/wallet/getchainparameters")
+            );
+            assert!(e.body.len() < 600);
+        }
+        other => panic!("expected HttpStatus, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn reports_gateway_auth_failures() {
+    let mock = Arc::new(MockTransport::new());
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .transport(mock.clone())
+        .retry_policy(RetryPolicy::none())
+        .auth(Auth::bearer("expired-token"))
+        .api_keys(vec!["first-api-key", "second-api-key"])
+        .build()
+        .unwrap();
+    mock.respond("GET", "/wallet/getnodeinfo", 401, "Unauthorized");
+    mock.respond(
+        "GET",
+        "/wallet/listnodes",
+        403,
+        "<html><head><title>403 Forbidden</title></head></html>",
+    );
+
+    match client.get_node_info().await {
+        Err(Error::HttpStatus(e)) => assert_eq!(e.status, 401),
+        other => panic!("expected HttpStatus, got {:?}", other),
+    }
+    match client.list_nodes().await {
+        Err(Error::HttpStatus(e)) => assert_eq!(e.status, 403),
+        other => panic!("expected HttpStatus, got {:?}", other),
+    }
+    assert_eq!(mock.requests().len(), 2);
+    assert!(client
+        .api_key_stats()
+        .iter()
+        .all(|stats| !stats.exhausted && stats.rejections == 0));
+}
+
+#[tokio::test]
+async fn backs_off_on_frequency_limit() {
+    let mock = Arc::new(MockTransport::new());
+    let limiter = Arc::new(RateLimiter::uniform(RateLimit::per_second(10.0)).unwrap());
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .transport(mock.clone())
+        .retry_policy(RetryPolicy::none())
+        .rate_limiter(limiter.clone())
+        .build()
+        .unwrap();
+    mock.respond(
+        "GET",
+        "/wallet/listnodes",
+        403,
+        r#"{"Success":false,"Error":"The key exceeds the frequency limit(15), and the query server is suspended for 30s","StatusCode":403}"#,
+    );
+
+    assert!(client.list_nodes().await.is_err());
+    assert_eq!(limiter.current_rate(EndpointClass::Wallet), Some(5.0));
+}
+
+#[tokio::test]
+async fn resends_with_the_next_api_key() {
+    let mock = Arc::new(MockTransport::new());
+    let client = Client::builder()
+        .base_url("http://node.test")
+        .transport(mock.clone())
+        .retry_policy(RetryPolicy::none())
+        .api_keys(vec!["first-api-key", "second-api-key"])
+        .build()
+        .unwrap();
+    mock.respond(
+        "POST",
+        "/wallet/broadcasthex",
+        403,
+        r#"{"Success":false,"Error":"The key exceeds the frequency limit(15), and the query server is suspended for 30s","StatusCode":403}"#,
+    );
+    mock.respond(
+        "POST",
+        "/wallet/broadcasthex",
+        200,
+        r#"{"result":true,"txid":"ab"}"#,
+    );
+
+    // Not retryable, but the first key was refused before the broadcast ran.
+    let response = client.broadcast_hex("00".into()).await.unwrap();
+    assert!(response.result);
+    let keys: Vec<_> = mock
+        .requests()
+        .iter()
+        .map(|r| r.headers["TRON-PRO-API-KEY"].to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(keys, vec!["first-api-key", "second-api-key"]);
+
+    // Once every key is exhausted the rejection is returned.
+    mock.respond("GET", "/wallet/listnodes", 429, "");
+    assert!(matches!(
+        client.list_nodes().await,
+        Err(Error::RateLimited(_))
+    ));
+    let sent = mock
+        .requests()
+        .iter()
+        .filter(|r| r.path() == "/wallet/listnodes")
+        .count();
+    assert_eq!(sent, 1);
+}